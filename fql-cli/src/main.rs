@@ -1,20 +1,48 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fs,
+    io::{self, BufRead},
+    path::PathBuf,
+    process,
+};
 
 use clap::{Parser, Subcommand};
 use fql::{
     ast::{Expr, Lit},
+    lint::Linter,
     parse,
+    schema::Schema,
+    CostWeights, Severity,
 };
 
 #[derive(Subcommand)]
 enum Cmd {
+    /// Print the diagnostics for FILTER, including schema diagnostics if --schema is set.
+    /// Exits with a non-zero status if there are any errors.
+    Check,
+    /// Compare FILTER to NEW and list the clauses which were added, removed, or changed.
+    Diff {
+        /// The updated FQL string to compare against FILTER.
+        new: String,
+    },
+    /// Read JSON records from stdin, one per line, and print those which match FILTER.
+    Eval,
     Facts,
+    /// Run the built-in lint rules over FILTER, which may start with `# fql-allow(...)`
+    /// lines. Exits with a non-zero status if there are any errors.
+    Lint,
     /// List the properties in FILTER (e.g. "host.online").
     ListProperties,
     /// List the operand values in FILTER (e.g. "true" or "'windows'").
     ListOperands,
+    /// Print complexity metrics for FILTER and its estimated cost.
+    Metrics,
     /// Print the debug representation of the tree, and any diagnostics.
     PrintTree,
+    /// Print FILTER with aliases and deprecated properties replaced, using the schema
+    /// from --schema.
+    Rewrite,
     /// List the unique literal values in FILTER in sorted order by type then value.
     SortLiterals,
 }
@@ -24,6 +52,9 @@ struct Opts {
     /// An FQL string, such as "host.online:true".
     #[clap(name = "FILTER")]
     filter: String,
+    /// A JSON, YAML, or TOML file describing the properties FILTER may use.
+    #[clap(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
     #[clap(subcommand)]
     command: Cmd,
 }
@@ -32,12 +63,84 @@ impl Opts {
     fn run(&self) {
         let parse_result = parse(&self.filter);
         let expr = parse_result.to_expr();
-        match self.command {
+        match &self.command {
+            Cmd::Check => {
+                let mut failed = false;
+                for diagnostic in parse_result.diagnostics() {
+                    println!("error: {diagnostic}");
+                    failed = true;
+                }
+
+                if let Some(schema) = self.load_schema() {
+                    for diagnostic in schema.validate(&parse_result) {
+                        println!("{}: {diagnostic}", diagnostic.severity());
+                        if let Some(fix) = diagnostic.fix() {
+                            println!("  help: {}", fix.title());
+                        }
+                        failed |= diagnostic.severity() == Severity::Error;
+                    }
+                }
+
+                if failed {
+                    process::exit(1);
+                }
+            }
+            Cmd::Diff { new } => {
+                print!("{}", fql::diff(&parse_result, &parse(new)));
+            }
+            Cmd::Eval => {
+                let Some(filter) = parse_result.to_filter() else {
+                    eprintln!("The eval command requires a filter without errors");
+                    process::exit(2);
+                };
+                let filter = filter.compile();
+
+                for (index, line) in io::stdin().lock().lines().enumerate() {
+                    let line = line.unwrap_or_else(|e| {
+                        eprintln!("Unable to read stdin: {e}");
+                        process::exit(2);
+                    });
+
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let record = match serde_json::from_str::<serde_json::Value>(&line) {
+                        Ok(record) => record,
+                        Err(e) => {
+                            eprintln!("Line {}: {e}", index + 1);
+                            continue;
+                        }
+                    };
+
+                    match filter.eval(&record) {
+                        Ok(true) => println!("{line}"),
+                        Ok(false) => {}
+                        Err(e) => eprintln!("Line {}: {e}", index + 1),
+                    }
+                }
+            }
             Cmd::Facts => match expr.unwrap() {
                 Expr::Binary(_) => println!("binary"),
                 Expr::Paren(_) => println!("parenthesized"),
                 Expr::Clause(_) => println!("clause"),
             },
+            Cmd::Lint => {
+                let diagnostics = Linter::new().run_source(&self.filter);
+                for diagnostic in &diagnostics {
+                    match diagnostic.rule() {
+                        Some(rule) => println!("{}[{rule}]: {diagnostic}", diagnostic.severity()),
+                        None => println!("{}: {diagnostic}", diagnostic.severity()),
+                    }
+                    if let Some(fix) = diagnostic.fix() {
+                        println!("  help: {}", fix.title());
+                    }
+                }
+
+                if diagnostics.iter().any(|d| d.severity() == Severity::Error) {
+                    process::exit(1);
+                }
+            }
             Cmd::ListProperties => {
                 for clause in expr.unwrap().clauses() {
                     if let Some(property) = clause.property() {
@@ -52,6 +155,16 @@ impl Opts {
                     }
                 }
             }
+            Cmd::Metrics => {
+                let metrics = parse_result.metrics();
+                println!("clauses: {}", metrics.clauses);
+                println!("max depth: {}", metrics.max_depth);
+                println!("max OR fan-out: {}", metrics.max_or_fan_out);
+                println!("wildcards: {}", metrics.wildcards);
+                println!("contains: {}", metrics.contains);
+                println!("distinct properties: {}", metrics.distinct_properties);
+                println!("cost: {}", metrics.cost(&CostWeights::default()));
+            }
             Cmd::PrintTree => {
                 println!("{}", parse_result.debug_tree());
 
@@ -59,9 +172,17 @@ impl Opts {
                     println!("{diagnostic}");
                 }
             }
+            Cmd::Rewrite => match self.load_schema() {
+                Some(schema) => println!("{}", schema.rewrite(&parse_result)),
+                None => {
+                    eprintln!("The rewrite command requires --schema");
+                    process::exit(2);
+                }
+            },
             Cmd::SortLiterals => {
                 let mut bools = BTreeSet::<bool>::new();
-                let mut ints = BTreeSet::<u64>::new();
+                // Sorting by length and then text orders integers of any size correctly.
+                let mut ints = BTreeSet::<(usize, String)>::new();
                 let mut strings = BTreeSet::<String>::new();
 
                 for value in expr
@@ -72,10 +193,7 @@ impl Opts {
                     match value {
                         Lit::Str(s) => strings.insert(s.value().into_owned()),
                         Lit::Bool(b) => bools.insert(b.value()),
-                        Lit::Int(i) => match i.value() {
-                            Ok(i) => ints.insert(i),
-                            Err(_) => false,
-                        },
+                        Lit::Int(i) => ints.insert((i.digits().len(), i.digits().to_string())),
                     };
                 }
 
@@ -83,7 +201,7 @@ impl Opts {
                     println!("{b}");
                 }
 
-                for i in ints {
+                for (_, i) in ints {
                     println!("{i}");
                 }
 
@@ -93,6 +211,28 @@ impl Opts {
             }
         }
     }
+
+    /// Read the file passed to `--schema`, exiting the process if it can't be loaded.
+    fn load_schema(&self) -> Option<Schema> {
+        let path = self.schema.as_ref()?;
+        match read_schema(path) {
+            Ok(schema) => Some(schema),
+            Err(e) => {
+                eprintln!("Unable to load schema from {}: {e}", path.display());
+                process::exit(2);
+            }
+        }
+    }
+}
+
+/// Read a schema, choosing the format from the file extension.
+fn read_schema(path: &PathBuf) -> Result<Schema, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => Ok(serde_yaml::from_str(&text)?),
+        Some("toml") => Ok(toml::from_str(&text)?),
+        _ => Ok(serde_json::from_str(&text)?),
+    }
 }
 
 fn main() {
@@ -0,0 +1,148 @@
+//! Derive macro for `fql::eval::Record`.
+//!
+//! Use this through the `derive` feature of `fql`, which re-exports the macro as
+//! `fql::eval::Record`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, LitStr, WherePredicate};
+
+/// Implement `fql::eval::Record` and `fql::eval::Field` for a struct with named fields.
+///
+/// Each field is a property with the same name, and its type must implement
+/// `fql::eval::Field`. Nested structs which derive `Record` can be used as fields,
+/// and are reached with dotted paths such as `device.os`.
+///
+/// # Attributes
+/// - `#[fql(rename = "name")]` uses a different property name for the field.
+/// - `#[fql(skip)]` leaves the field out, so filters treat it as missing.
+///
+/// # Example
+/// ```rust
+/// use fql::eval::Record;
+///
+/// #[derive(Record)]
+/// struct Device {
+///     #[fql(rename = "platform_name")]
+///     platform: String,
+///     tags: Vec<String>,
+/// }
+///
+/// #[derive(Record)]
+/// struct Host {
+///     hostname: String,
+///     risk: Option<u32>,
+///     device: Device,
+///     #[fql(skip)]
+///     #[allow(dead_code)]
+///     secret: String,
+/// }
+///
+/// let host = Host {
+///     hostname: "web-01".into(),
+///     risk: Some(72),
+///     device: Device {
+///         platform: "Windows".into(),
+///         tags: vec!["prod".into(), "dmz".into()],
+///     },
+///     secret: "hunter2".into(),
+/// };
+///
+/// let filter = fql::parse("device.platform_name:'windows'+device.tags:['dmz']+risk:>50")
+///     .to_filter()
+///     .unwrap();
+/// assert!(filter.eval(&host).unwrap());
+///
+/// let filter = fql::parse("secret:'*'").to_filter().unwrap();
+/// assert!(!filter.eval(&host).unwrap());
+/// ```
+#[proc_macro_derive(Record, attributes(fql))]
+pub fn derive_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Record can only be derived for structs",
+        ));
+    };
+
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Record can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut arms = Vec::new();
+    let mut bounds = Vec::<WherePredicate>::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let mut name = ident.to_string();
+        let mut skip = false;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("fql"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename` or `skip`"))
+                }
+            })?;
+        }
+
+        if skip {
+            continue;
+        }
+
+        // Bounds are only needed when a field's type may depend on a type parameter.
+        if !input.generics.params.is_empty() {
+            let ty = &field.ty;
+            bounds.push(parse_quote!(#ty: ::fql::eval::Field));
+        }
+        arms.push(quote! {
+            #name => ::fql::eval::Field::field(&self.#ident, rest),
+        });
+    }
+
+    let where_clause = input.generics.make_where_clause();
+    where_clause.predicates.extend(bounds);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::fql::eval::Field for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn field(&self, path: &[::std::string::String]) -> ::fql::eval::FieldValue<'_> {
+                match path.split_first() {
+                    ::std::option::Option::Some((first, rest)) => match first.as_str() {
+                        #(#arms)*
+                        _ => ::fql::eval::FieldValue::Missing,
+                    },
+                    ::std::option::Option::None => ::fql::eval::FieldValue::Object,
+                }
+            }
+        }
+
+        impl #impl_generics ::fql::eval::Record for #ident #ty_generics #where_clause {
+            fn get(&self, path: &::fql::PropertyPath) -> ::fql::eval::FieldValue<'_> {
+                ::fql::eval::Field::field(self, path.segments())
+            }
+        }
+    })
+}
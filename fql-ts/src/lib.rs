@@ -6,6 +6,19 @@ pub fn parse(input: &str) -> Parse {
     Parse(fql::parse(input))
 }
 
+/// Compare the clauses of two filters, aligning them by property path.
+///
+/// Returns a list of `ClauseChange` objects.
+#[wasm_bindgen]
+pub fn diff(old: &Parse, updated: &Parse) -> Vec<JsValue> {
+    fql::diff(&old.0, &updated.0)
+        .changes()
+        .iter()
+        .map(ClauseChange::from)
+        .map(JsValue::from)
+        .collect()
+}
+
 #[wasm_bindgen]
 pub struct Parse(fql::Parse);
 
@@ -35,6 +48,35 @@ impl Parse {
             .map(JsValue::from)
             .collect::<Vec<JsValue>>()
     }
+
+    /// Get the innermost node at a cursor position, measured in bytes from the
+    /// start of the input.
+    #[wasm_bindgen(js_name = "nodeAtOffset")]
+    pub fn node_at_offset(&self, offset: u32) -> Option<Node> {
+        self.0.node_at_offset(offset.into()).map(Node)
+    }
+
+    /// Get the text of the token at a cursor position, measured in bytes from the
+    /// start of the input.
+    #[wasm_bindgen(js_name = "tokenAtOffset")]
+    pub fn token_at_offset(&self, offset: u32) -> Option<Token> {
+        self.0.token_at_offset(offset.into()).map(|token| Token {
+            text: token.text().to_string(),
+            range: TextRange(token.text_range()),
+        })
+    }
+
+    /// Get the innermost node which completely contains the range from `start` to `end`.
+    #[wasm_bindgen(js_name = "coveringElement")]
+    pub fn covering_element(&self, start: u32, end: u32) -> Option<Node> {
+        if start > end {
+            return None;
+        }
+
+        self.0
+            .covering_element(fql::TextRange::new(start.into(), end.into()))
+            .map(Node)
+    }
 }
 
 #[wasm_bindgen]
@@ -46,7 +88,7 @@ impl Expr {
     #[wasm_bindgen(js_name = "asBinary")]
     pub fn as_binary(&self) -> Option<ExprBinary> {
         if let ast::Expr::Binary(v) = &self.0 {
-            Some(v.clone()).map(ExprBinary)
+            Some(ExprBinary(v.clone()))
         } else {
             None
         }
@@ -55,7 +97,7 @@ impl Expr {
     #[wasm_bindgen(js_name = "asClause")]
     pub fn as_clause(&self) -> Option<Clause> {
         if let ast::Expr::Clause(v) = &self.0 {
-            Some(v.clone()).map(Clause)
+            Some(Clause(v.clone()))
         } else {
             None
         }
@@ -64,11 +106,102 @@ impl Expr {
     #[wasm_bindgen(js_name = "asParen")]
     pub fn as_paren(&self) -> Option<ExprParen> {
         if let ast::Expr::Paren(v) = &self.0 {
-            Some(v.clone()).map(ExprParen)
+            Some(ExprParen(v.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Any node in the syntax tree, as found by a position-based lookup.
+#[wasm_bindgen]
+pub struct Node(ast::Node);
+
+#[wasm_bindgen]
+impl Node {
+    /// One of `"binary"`, `"paren"`, `"clause"`, `"property"`, `"operand"`,
+    /// `"literal"`, or `"placeholder"`.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        match self.0 {
+            ast::Node::Binary(_) => "binary",
+            ast::Node::Paren(_) => "paren",
+            ast::Node::Clause(_) => "clause",
+            ast::Node::Property(_) => "property",
+            ast::Node::Operand(_) => "operand",
+            ast::Node::Literal(_) => "literal",
+            ast::Node::Placeholder(_) => "placeholder",
+        }
+        .to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn range(&self) -> TextRange {
+        TextRange(self.0.span())
+    }
+
+    /// The innermost node which contains this one.
+    #[wasm_bindgen(getter)]
+    pub fn parent(&self) -> Option<Node> {
+        self.0.parent().map(Node)
+    }
+
+    #[wasm_bindgen(js_name = "asExpr")]
+    pub fn as_expr(&self) -> Option<Expr> {
+        self.0.as_expr().map(Expr)
+    }
+
+    #[wasm_bindgen(js_name = "asProperty")]
+    pub fn as_property(&self) -> Option<Property> {
+        if let ast::Node::Property(v) = &self.0 {
+            Some(Property(v.clone()))
         } else {
             None
         }
     }
+
+    #[wasm_bindgen(js_name = "asOperand")]
+    pub fn as_operand(&self) -> Option<Operand> {
+        if let ast::Node::Operand(v) = &self.0 {
+            Some(Operand(v.clone()))
+        } else {
+            None
+        }
+    }
+
+    #[wasm_bindgen(js_name = "asLiteral")]
+    pub fn as_literal(&self) -> Option<Literal> {
+        if let ast::Node::Literal(v) = &self.0 {
+            Some(Literal(v.clone()))
+        } else {
+            None
+        }
+    }
+
+    #[wasm_bindgen(js_name = "toString")]
+    pub fn to_js_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// A single token of the input, such as an identifier or operator.
+#[wasm_bindgen]
+pub struct Token {
+    text: String,
+    range: TextRange,
+}
+
+#[wasm_bindgen]
+impl Token {
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn range(&self) -> TextRange {
+        self.range.clone()
+    }
 }
 
 /// A binary expression, such as `os:'windows'+online:true`.
@@ -86,6 +219,18 @@ impl ExprBinary {
     pub fn rhs(&self) -> Option<Expr> {
         self.0.rhs().map(Expr)
     }
+
+    /// A list of `Expr` objects joined by this expression's operator, in source
+    /// order, flattening chains of the same operator such as `a:1+b:2+c:3`.
+    #[wasm_bindgen(getter, js_name = "chainOperands")]
+    pub fn chain_operands(&self) -> Vec<JsValue> {
+        self.0
+            .chain_operands()
+            .into_iter()
+            .map(Expr)
+            .map(JsValue::from)
+            .collect()
+    }
 }
 
 /// A single property, operator, and operand, such as `online:true`.
@@ -122,7 +267,7 @@ pub struct Property(ast::Property);
 #[wasm_bindgen]
 impl Property {
     #[wasm_bindgen(js_name = "toString")]
-    pub fn to_string(&self) -> String {
+    pub fn to_js_string(&self) -> String {
         self.0.to_string()
     }
 }
@@ -141,9 +286,10 @@ impl Operand {
 #[wasm_bindgen(typescript_custom_section)]
 const LIT_VALUE: &'static str = r#"
 /**
- * The value of a literal.
+ * The value of a literal. Integers which are not safely representable as a
+ * `number` are returned as a `bigint`.
  */
-export type LitValue = string | number | boolean;
+export type LitValue = string | number | boolean | bigint;
 "#;
 
 #[wasm_bindgen]
@@ -152,6 +298,9 @@ extern "C" {
     pub type LitValueOrUndefined;
 }
 
+/// The largest integer which a JS `number` can represent exactly, `2^53 - 1`.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
 /// A literal value, such as `true`, `5`, or `'falcon'`.
 #[wasm_bindgen]
 pub struct Literal(ast::Literal);
@@ -170,8 +319,8 @@ impl Literal {
                 ast::Lit::Str(s) => JsValue::from_str(&s.value()),
                 ast::Lit::Bool(b) => JsValue::from_bool(b.value()),
                 ast::Lit::Int(i) => match i.value() {
-                    Ok(v) => JsValue::from_f64(v as f64),
-                    Err(_) => JsValue::from_f64(f64::NAN),
+                    Ok(v) if v <= MAX_SAFE_INTEGER => JsValue::from_f64(v as f64),
+                    _ => JsValue::bigint_from_str(i.digits()),
                 },
             })
             .unwrap_or(JsValue::UNDEFINED)
@@ -179,6 +328,90 @@ impl Literal {
     }
 }
 
+/// A clause which was added, removed, or changed between two filters, or a change to
+/// how the clauses are combined.
+#[wasm_bindgen]
+pub struct ClauseChange(fql::Change);
+
+#[wasm_bindgen]
+impl ClauseChange {
+    /// One of `"added"`, `"removed"`, `"changed"`, or `"regrouped"`.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        match self.0 {
+            fql::Change::Added(_) => "added",
+            fql::Change::Removed(_) => "removed",
+            fql::Change::Changed { .. } => "changed",
+            fql::Change::Regrouped { .. } => "regrouped",
+        }
+        .to_string()
+    }
+
+    /// The property of the clause, which is undefined for a regrouping.
+    #[wasm_bindgen(getter)]
+    pub fn property(&self) -> Option<String> {
+        self.0.property().map(ToString::to_string)
+    }
+
+    /// The clause text in the old filter, if it existed there, or the whole old
+    /// filter for a regrouping.
+    #[wasm_bindgen(getter, js_name = "oldText")]
+    pub fn old_text(&self) -> Option<String> {
+        match &self.0 {
+            fql::Change::Regrouped { old, .. } => Some(old.clone()),
+            change => change.before().map(|c| c.text.clone()),
+        }
+    }
+
+    /// The range of the clause in the old filter, if it existed there, or
+    /// of the whole old filter for a regrouping.
+    #[wasm_bindgen(getter, js_name = "oldRange")]
+    pub fn old_range(&self) -> Option<TextRange> {
+        match &self.0 {
+            fql::Change::Regrouped { old_span, .. } => Some(TextRange(*old_span)),
+            change => change.before().map(|c| TextRange(c.span)),
+        }
+    }
+
+    /// The clause text in the new filter, if it exists there, or the whole new
+    /// filter for a regrouping.
+    #[wasm_bindgen(getter, js_name = "newText")]
+    pub fn new_text(&self) -> Option<String> {
+        match &self.0 {
+            fql::Change::Regrouped { new, .. } => Some(new.clone()),
+            change => change.after().map(|c| c.text.clone()),
+        }
+    }
+
+    /// The range of the clause in the new filter, if it exists there, or
+    /// of the whole new filter for a regrouping.
+    #[wasm_bindgen(getter, js_name = "newRange")]
+    pub fn new_range(&self) -> Option<TextRange> {
+        match &self.0 {
+            fql::Change::Regrouped { new_span, .. } => Some(TextRange(*new_span)),
+            change => change.after().map(|c| TextRange(c.span)),
+        }
+    }
+
+    /// Whether a changed clause uses a different operator.
+    #[wasm_bindgen(getter, js_name = "operatorChanged")]
+    pub fn operator_changed(&self) -> bool {
+        self.0.operator_changed()
+    }
+
+    /// Render the change as a line of text, such as `~ a:>5 -> a:>=5`.
+    #[wasm_bindgen(js_name = "toString")]
+    pub fn to_js_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl<'a> From<&'a fql::Change> for ClauseChange {
+    fn from(change: &'a fql::Change) -> Self {
+        Self(change.clone())
+    }
+}
+
 // This doesn't use wasm_bindgen(getter_with_clone) due to known issue
 // with `readonly` https://github.com/rustwasm/wasm-bindgen/issues/2721
 #[wasm_bindgen]
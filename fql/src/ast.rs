@@ -1,9 +1,15 @@
 mod expr;
 mod literal;
+mod node;
+mod placeholder;
 mod property;
 
-pub use self::expr::{Clause, Expr, ExprBinary, ExprParen, Operand};
+pub use self::expr::{
+    BooleanContext, Clause, ClauseOperator, Expr, ExprBinary, ExprParen, Operand,
+};
 pub use self::literal::{Lit, LitBool, LitInt, LitStr, Literal};
+pub use self::node::Node;
+pub use self::placeholder::Placeholder;
 pub use self::property::Property;
 
 #[macro_export]
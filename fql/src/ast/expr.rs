@@ -1,4 +1,4 @@
-use std::iter::{self, Chain, Once};
+use std::fmt;
 
 use rowan::ast::AstNode;
 
@@ -8,7 +8,7 @@ use crate::{
     Spanned,
 };
 
-use super::{Literal, Property};
+use super::{Literal, Placeholder, Property};
 
 #[derive(Debug, Clone)]
 pub enum Expr {
@@ -38,39 +38,116 @@ impl Expr {
     /// );
     /// ```
     pub fn clauses(&self) -> impl Iterator<Item = Clause> {
-        Clauses::new(&self)
+        Clauses::new(self)
     }
-}
 
-enum Clauses {
-    Binary(Chain<Box<Clauses>, Box<Clauses>>),
-    Single(Once<Clause>),
-    None,
-}
+    /// The expression which directly contains this one, if any.
+    pub fn parent_expr(&self) -> Option<Expr> {
+        self.syntax().parent().and_then(Expr::cast)
+    }
 
-impl Clauses {
-    fn new(expr: &Expr) -> Self {
-        match expr {
-            Expr::Binary(bin) => Self::binary(bin.lhs(), bin.rhs()),
-            Expr::Paren(paren) => Self::new_owned(paren.body()),
-            Expr::Clause(_) => Self::new_owned(Some(expr.clone())),
+    /// The boolean operator which joins this expression to its siblings.
+    ///
+    /// Parentheses are transparent, so the clause `b:2` in `a:1+(b:2)` is in an
+    /// `And` context. An expression which is not an operand of `+` or `,` is at the `Root`.
+    pub fn boolean_context(&self) -> BooleanContext {
+        match self.operand_of() {
+            Some(binary) => binary.context(),
+            None => BooleanContext::Root,
         }
     }
 
-    fn new_owned(expr: Option<Expr>) -> Self {
+    /// The other operands combined with this expression by the same boolean operator.
+    ///
+    /// Chains of the same operator are flattened, including through parentheses, so
+    /// the siblings of `b:2` in `a:1+(b:2+c:3)` are `a:1` and `c:3`. Operands of a
+    /// different operator are returned whole, so the siblings of `a:1` in
+    /// `a:1+b:2,c:3` are just `b:2`. Siblings are returned in source order.
+    ///
+    /// # Example
+    /// ```rust
+    /// let expr = fql::parse("a:1+(b:2,c:3)+d:4").to_expr().unwrap();
+    /// let first = expr.clauses().find(|c| c.to_string() == "a:1").unwrap();
+    ///
+    /// let siblings = first.siblings().iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(siblings, ["(b:2,c:3)", "d:4"]);
+    /// ```
+    pub fn siblings(&self) -> Vec<Expr> {
+        let Some(binary) = self.operand_of() else {
+            return Vec::new();
+        };
+
+        let member = self.outermost_paren();
+        binary
+            .chain_root()
+            .chain_operands()
+            .into_iter()
+            .filter(|expr| expr.syntax() != member.syntax())
+            .collect()
+    }
+
+    /// The number of boolean groups which contain this expression.
+    ///
+    /// This matches the nesting of the lowered [`Filter`](crate::Filter): a
+    /// whole-input clause has depth 0, each clause of `a:1+b:2` has depth 1, and `c:3`
+    /// in `a:1,b:2+c:3` has depth 2. Redundant parentheses do not add depth.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut current = self.operand_of();
+        while let Some(binary) = current {
+            depth += 1;
+            current = Expr::Binary(binary.chain_root()).operand_of();
+        }
+
+        depth
+    }
+
+    /// This expression, or the outermost parentheses which only wrap it.
+    pub(crate) fn outermost_paren(&self) -> Expr {
+        let mut expr = self.clone();
+        while let Some(parent @ Expr::Paren(_)) = expr.parent_expr() {
+            expr = parent;
+        }
+
+        expr
+    }
+
+    /// The binary expression this expression is an operand of, looking through parentheses.
+    fn operand_of(&self) -> Option<ExprBinary> {
+        match self.outermost_paren().parent_expr() {
+            Some(Expr::Binary(binary)) => Some(binary),
+            _ => None,
+        }
+    }
+
+    /// The binary expression this expression represents if it continues a chain of
+    /// `context`, looking through parentheses.
+    fn chain_link(&self, context: BooleanContext) -> Option<ExprBinary> {
+        let mut expr = self.clone();
+        while let Expr::Paren(paren) = expr {
+            expr = paren.body()?;
+        }
+
         match expr {
-            Some(Expr::Clause(clause)) => Self::Single(iter::once(clause)),
-            Some(Expr::Binary(binary)) => Self::Binary(
-                Box::new(Self::new_owned(binary.lhs()))
-                    .chain(Box::new(Self::new_owned(binary.rhs()))),
-            ),
-            Some(Expr::Paren(paren)) => Self::new_owned(paren.body()),
-            None => Self::None,
+            Expr::Binary(binary) if binary.context() == context => Some(binary),
+            _ => None,
         }
     }
+}
+
+/// Iterator over the clauses of an expression.
+///
+/// This walks the tree with an explicit stack rather than recursion, so deeply nested
+/// expressions can't overflow the call stack.
+struct Clauses {
+    stack: Vec<Expr>,
+}
 
-    fn binary(lhs: Option<Expr>, rhs: Option<Expr>) -> Self {
-        Self::Binary(Box::new(Self::new_owned(lhs)).chain(Box::new(Self::new_owned(rhs))))
+impl Clauses {
+    fn new(expr: &Expr) -> Self {
+        Self {
+            stack: vec![expr.clone()],
+        }
     }
 }
 
@@ -78,11 +155,17 @@ impl Iterator for Clauses {
     type Item = Clause;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            Clauses::Binary(v) => v.next(),
-            Clauses::Single(v) => v.next(),
-            Clauses::None => None,
+        while let Some(expr) = self.stack.pop() {
+            match expr {
+                Expr::Binary(binary) => self
+                    .stack
+                    .extend(binary.rhs().into_iter().chain(binary.lhs())),
+                Expr::Paren(paren) => self.stack.extend(paren.body()),
+                Expr::Clause(clause) => return Some(clause),
+            }
         }
+
+        None
     }
 }
 
@@ -114,6 +197,12 @@ impl AstNode for Expr {
     }
 }
 
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.syntax().fmt(f)
+    }
+}
+
 impl Spanned for Expr {
     fn span(&self) -> rowan::TextRange {
         match self {
@@ -131,16 +220,84 @@ impl ExprBinary {
         self.0.children().find_map(Expr::cast)
     }
 
+    /// The `+` or `,` token joining the two sides of the expression.
     pub fn op(&self) -> Option<SyntaxToken> {
         self.0
-            .descendants_with_tokens()
+            .children_with_tokens()
             .filter_map(SyntaxElement::into_token)
-            .find(|t| t.kind() == SyntaxKind::Comma || t.kind() == SyntaxKind::Colon)
+            .find(|t| t.kind() == SyntaxKind::Plus || t.kind() == SyntaxKind::Comma)
     }
 
     pub fn rhs(&self) -> Option<Expr> {
         self.0.children().filter_map(Expr::cast).nth(1)
     }
+
+    /// The two sides of the expression, in source order.
+    ///
+    /// A side is skipped if it is missing. Use [`ExprBinary::chain_operands`] to get
+    /// every operand of a chain such as `a:1+b:2+c:3`.
+    pub fn operands(&self) -> impl Iterator<Item = Expr> {
+        self.lhs().into_iter().chain(self.rhs())
+    }
+
+    /// The boolean operator applied by the expression.
+    ///
+    /// Returns [`BooleanContext::Root`] if the operator is missing.
+    pub fn context(&self) -> BooleanContext {
+        match self.op().map(|op| op.kind()) {
+            Some(SyntaxKind::Plus) => BooleanContext::And,
+            Some(SyntaxKind::Comma) => BooleanContext::Or,
+            _ => BooleanContext::Root,
+        }
+    }
+
+    /// Whether this is the outermost expression in its chain of operators, so it
+    /// isn't an operand of the same operator.
+    pub(crate) fn is_chain_root(&self) -> bool {
+        Expr::Binary(self.clone()).boolean_context() != self.context()
+    }
+
+    /// The operands of the chain of operators below this expression, in source order.
+    ///
+    /// Operands joined by the same operator are flattened, including through
+    /// parentheses, and operands of a different operator are returned whole.
+    ///
+    /// # Example
+    /// ```rust
+    /// let expr = fql::parse("a:1+(b:2,c:3)+d:4").to_expr().unwrap();
+    /// let fql::ast::Expr::Binary(binary) = expr else { unreachable!() };
+    ///
+    /// let operands = binary.chain_operands().iter().map(|o| o.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(operands, ["a:1", "(b:2,c:3)", "d:4"]);
+    /// ```
+    pub fn chain_operands(&self) -> Vec<Expr> {
+        let context = self.context();
+        let mut operands = Vec::new();
+        let mut stack = vec![Expr::Binary(self.clone())];
+        while let Some(expr) = stack.pop() {
+            match expr.chain_link(context) {
+                Some(link) => stack.extend(link.rhs().into_iter().chain(link.lhs())),
+                None => operands.push(expr),
+            }
+        }
+
+        operands
+    }
+
+    /// The outermost expression in the chain of operators this one belongs to.
+    fn chain_root(&self) -> ExprBinary {
+        let context = self.context();
+        let mut root = self.clone();
+        while let Some(parent) = Expr::Binary(root.clone()).operand_of() {
+            if parent.context() != context {
+                break;
+            }
+
+            root = parent;
+        }
+
+        root
+    }
 }
 
 ast_node!(ExprParen from ParenExpr);
@@ -165,6 +322,10 @@ impl Clause {
             .find(|t| t.kind() == SyntaxKind::Colon)
     }
 
+    /// The token of the clause's explicit operator, if one is present.
+    ///
+    /// Equality clauses such as `online:true` have no operator token; use
+    /// [`Clause::operator_kind`] to get the operator the clause applies.
     pub fn operator(&self) -> Option<SyntaxToken> {
         const OPERATORS: &[SyntaxKind] = &[
             SyntaxKind::Bang,
@@ -179,12 +340,140 @@ impl Clause {
         self.0
             .descendants_with_tokens()
             .filter_map(SyntaxElement::into_token)
-            .find(|t| OPERATORS.contains(&&t.kind()))
+            .find(|t| OPERATORS.contains(&t.kind()))
+    }
+
+    /// The comparison the clause applies to its operand.
+    ///
+    /// Clauses without an explicit operator token are equality comparisons.
+    pub fn operator_kind(&self) -> ClauseOperator {
+        self.operator()
+            .and_then(|t| ClauseOperator::from_kind(t.kind()))
+            .unwrap_or(ClauseOperator::Eq)
     }
 
     pub fn operand(&self) -> Option<Operand> {
         self.0.children().find_map(Operand::cast)
     }
+
+    /// The expression which directly contains this clause. See [`Expr::parent_expr`].
+    pub fn parent_expr(&self) -> Option<Expr> {
+        Expr::Clause(self.clone()).parent_expr()
+    }
+
+    /// The boolean operator which joins this clause to its siblings. See [`Expr::boolean_context`].
+    pub fn boolean_context(&self) -> BooleanContext {
+        Expr::Clause(self.clone()).boolean_context()
+    }
+
+    /// The other operands combined with this clause. See [`Expr::siblings`].
+    pub fn siblings(&self) -> Vec<Expr> {
+        Expr::Clause(self.clone()).siblings()
+    }
+
+    /// The number of boolean groups which contain this clause. See [`Expr::depth`].
+    pub fn depth(&self) -> usize {
+        Expr::Clause(self.clone()).depth()
+    }
+}
+
+/// The boolean operator which combines an expression with its siblings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BooleanContext {
+    /// The expression is an operand of `+`.
+    And,
+    /// The expression is an operand of `,`.
+    Or,
+    /// The expression is not combined with anything.
+    Root,
+}
+
+/// The comparison a [`Clause`] applies between its property and operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ClauseOperator {
+    /// `property:value`
+    Eq,
+    /// `property:!value`
+    Ne,
+    /// `property:>value`
+    Gt,
+    /// `property:>=value`
+    Ge,
+    /// `property:<value`
+    Lt,
+    /// `property:<=value`
+    Le,
+    /// `property:~value`
+    Contains,
+    /// `property:!~value`
+    NotContains,
+}
+
+impl ClauseOperator {
+    fn from_kind(kind: SyntaxKind) -> Option<Self> {
+        Some(match kind {
+            SyntaxKind::Bang => Self::Ne,
+            SyntaxKind::Gt => Self::Gt,
+            SyntaxKind::Ge => Self::Ge,
+            SyntaxKind::Lt => Self::Lt,
+            SyntaxKind::Le => Self::Le,
+            SyntaxKind::Tilde => Self::Contains,
+            SyntaxKind::BangTilde => Self::NotContains,
+            _ => return None,
+        })
+    }
+
+    /// The operator as it appears in FQL text, between the colon and the operand.
+    ///
+    /// This is the empty string for [`ClauseOperator::Eq`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "",
+            Self::Ne => "!",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Contains => "~",
+            Self::NotContains => "!~",
+        }
+    }
+
+    /// The operator which matches exactly the values this one rejects.
+    pub fn negate(self) -> Self {
+        match self {
+            Self::Eq => Self::Ne,
+            Self::Ne => Self::Eq,
+            Self::Gt => Self::Le,
+            Self::Ge => Self::Lt,
+            Self::Lt => Self::Ge,
+            Self::Le => Self::Gt,
+            Self::Contains => Self::NotContains,
+            Self::NotContains => Self::Contains,
+        }
+    }
+
+    /// Whether this is `!` or `!~`, which match records the positive form rejects,
+    /// including records which lack the property.
+    pub fn is_negated(self) -> bool {
+        matches!(self, Self::Ne | Self::NotContains)
+    }
+
+    /// Whether this is one of the ordered comparisons `>`, `>=`, `<`, or `<=`.
+    pub fn is_range(self) -> bool {
+        matches!(self, Self::Gt | Self::Ge | Self::Lt | Self::Le)
+    }
+}
+
+impl fmt::Display for ClauseOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 ast_node!(Operand);
@@ -201,13 +490,18 @@ impl Operand {
     pub fn literal(&self) -> Option<Literal> {
         self.0.descendants().find_map(Literal::cast)
     }
+
+    /// The placeholder in the operand, if it has one instead of a literal.
+    pub fn placeholder(&self) -> Option<Placeholder> {
+        self.0.descendants().find_map(Placeholder::cast)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::parse;
 
-    use super::{Clause, Expr};
+    use super::{BooleanContext, Clause, ClauseOperator, Expr};
 
     /// Parse a string as an expression and make sure it's a clause.
     #[track_caller]
@@ -227,6 +521,100 @@ mod tests {
             .is_exact());
     }
 
+    #[test]
+    fn operator_kind() {
+        assert_eq!(clause("online:true").operator_kind(), ClauseOperator::Eq);
+        assert_eq!(clause("version:>=5").operator_kind(), ClauseOperator::Ge);
+        assert_eq!(
+            clause("hostname:!~'win'").operator_kind(),
+            ClauseOperator::NotContains
+        );
+    }
+
+    #[test]
+    fn binary_op() {
+        let op = |input: &str| match parse(input).to_expr().unwrap() {
+            Expr::Binary(binary) => binary.op().map(|t| t.text().to_string()),
+            _ => panic!("Expression was not binary"),
+        };
+
+        // The operator was once found among all descendants, which returned the
+        // `:` of the first clause or a `,` nested in parentheses.
+        assert_eq!(op("a:1+b:2").as_deref(), Some("+"));
+        assert_eq!(op("a:1,b:2").as_deref(), Some(","));
+        assert_eq!(op("(a:1,b:2)+c:3").as_deref(), Some("+"));
+        assert_eq!(op("a:1+(b:2,c:3)").as_deref(), Some("+"));
+    }
+
+    /// Find the clause whose text is `text` in `input`.
+    #[track_caller]
+    fn clause_in(input: &str, text: &str) -> Clause {
+        parse(input)
+            .to_expr()
+            .unwrap()
+            .clauses()
+            .find(|c| c.to_string() == text)
+            .unwrap()
+    }
+
+    #[track_caller]
+    fn sibling_text(input: &str, text: &str) -> Vec<String> {
+        clause_in(input, text)
+            .siblings()
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn boolean_context() {
+        assert_eq!(clause("a:1").boolean_context(), BooleanContext::Root);
+        assert_eq!(
+            clause_in("(a:1)", "a:1").boolean_context(),
+            BooleanContext::Root
+        );
+        assert_eq!(
+            clause_in("a:1,b:2+c:3", "a:1").boolean_context(),
+            BooleanContext::Or
+        );
+        assert_eq!(
+            clause_in("a:1,b:2+c:3", "c:3").boolean_context(),
+            BooleanContext::And
+        );
+        assert_eq!(
+            clause_in("a:1+((b:2))", "b:2").boolean_context(),
+            BooleanContext::And
+        );
+    }
+
+    #[test]
+    fn parent_expr() {
+        let b = clause_in("a:1+(b:2)", "b:2");
+        assert!(matches!(b.parent_expr(), Some(Expr::Paren(_))));
+        assert!(clause("a:1").parent_expr().is_none());
+    }
+
+    #[test]
+    fn siblings() {
+        assert_eq!(sibling_text("a:1+b:2+c:3", "b:2"), ["a:1", "c:3"]);
+        assert_eq!(sibling_text("a:1+(b:2+c:3)", "c:3"), ["a:1", "b:2"]);
+        assert_eq!(sibling_text("a:1,b:2+c:3", "a:1"), ["b:2+c:3"]);
+        assert_eq!(sibling_text("a:1,b:2+c:3", "b:2"), ["c:3"]);
+        assert_eq!(sibling_text("a:1+(b:2)", "b:2"), ["a:1"]);
+        assert!(clause("a:1").siblings().is_empty());
+    }
+
+    #[test]
+    fn depth() {
+        assert_eq!(clause("a:1").depth(), 0);
+        assert_eq!(clause_in("((a:1))", "a:1").depth(), 0);
+        assert_eq!(clause_in("a:1+b:2+c:3", "a:1").depth(), 1);
+        assert_eq!(clause_in("a:1+(b:2+c:3)", "c:3").depth(), 1);
+        assert_eq!(clause_in("a:1,b:2+c:3", "a:1").depth(), 1);
+        assert_eq!(clause_in("a:1,b:2+c:3", "c:3").depth(), 2);
+        assert_eq!(clause_in("a:1+(b:2,(c:3+d:4))", "d:4").depth(), 3);
+    }
+
     #[test]
     fn operand_unclosed_bracket_is_exact() {
         assert!(clause("host.platform:['windows'")
@@ -1,14 +1,82 @@
-use std::{borrow::Cow, num::ParseIntError};
+use std::{borrow::Cow, num::ParseIntError, time::SystemTime};
+
+use rowan::ast::AstNode;
 
 use crate::{
-    ast_node,
+    ast_node, grammar,
+    parser::parse_with,
+    quote::quote_str,
     syntax::{SyntaxElement, SyntaxKind, SyntaxToken},
-    Spanned,
+    time::format_timestamp,
+    ParseOptions, Spanned, Value,
 };
 
 ast_node!(Literal);
 
 impl Literal {
+    /// Create a literal for a lowered value.
+    ///
+    /// The literal's [`value`](Self::value) is guaranteed to equal `value`, and its
+    /// `Display` output is valid FQL literal text.
+    ///
+    /// Returns `None` if `value` is a string containing a single quote, which an FQL
+    /// string literal can't hold.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::{ast::Lit, Literal, Value};
+    ///
+    /// let literal = Literal::from_value(&Value::from(r"C:\temp")).unwrap();
+    /// assert_eq!(literal.to_string(), r"'C:\temp'");
+    ///
+    /// let Some(Lit::Str(s)) = literal.value() else { panic!() };
+    /// assert_eq!(s.value(), r"C:\temp");
+    ///
+    /// assert!(Literal::from_value(&Value::from("O'Brien")).is_none());
+    /// ```
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Self::from_string(s),
+            Value::Int(i) => Some(Self::from_u64(*i)),
+            Value::Bool(b) => Some(Self::from_bool(*b)),
+        }
+    }
+
+    /// Create a string literal by quoting `value`.
+    ///
+    /// Returns `None` if `value` contains a single quote; see [`quote_str`].
+    pub fn from_string(value: &str) -> Option<Self> {
+        quote_str(value).map(|text| Self::from_text(&text))
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_text(&value.to_string())
+    }
+
+    pub fn from_bool(value: bool) -> Self {
+        Self::from_text(if value { "true" } else { "false" })
+    }
+
+    /// Create a string literal holding `time` as an RFC 3339 timestamp in UTC,
+    /// such as `'2022-04-01T12:30:00Z'`. Sub-second precision is truncated.
+    pub fn from_timestamp(time: SystemTime) -> Self {
+        Self::from_string(&format_timestamp(time)).expect("Timestamps don't contain quotes")
+    }
+
+    /// Parse text which is known to be exactly one valid literal.
+    fn from_text(text: &str) -> Self {
+        let parse = parse_with(text, ParseOptions::default(), grammar::literal);
+        debug_assert!(
+            parse.diagnostics().next().is_none(),
+            "{text} is not a literal"
+        );
+        parse
+            .syntax()
+            .first_child()
+            .and_then(Self::cast)
+            .expect("Generated text should parse as a literal")
+    }
+
     pub fn value(&self) -> Option<Lit> {
         self.0
             .descendants_with_tokens()
@@ -61,7 +129,7 @@ impl LitStr {
     /// Get the value of the string, without leading or trailing quotation marks.
     ///
     /// If escape characters are allowed in strings, they will be unescaped in this function's return value.
-    pub fn value(&self) -> Cow<str> {
+    pub fn value(&self) -> Cow<'_, str> {
         let text = self.0.text();
         debug_assert!(text.starts_with('\''));
         debug_assert!(text.ends_with('\''));
@@ -97,8 +165,8 @@ impl LitBool {
     /// that violates a precondition of `LitBool` construction.
     pub fn value(&self) -> bool {
         match self.0.text() {
-            t if t == "true" => true,
-            t if t == "false" => false,
+            "true" => true,
+            "false" => false,
             word => panic!("Expected 'true' or 'false', got '{word}'"),
         }
     }
@@ -126,10 +194,30 @@ impl LitInt {
     ///
     /// # Errors
     /// This function may return an error if the value is a well-formed integer that
-    /// cannot be parsed into a `u64`.
+    /// cannot be parsed into a `u64`. The parser reports a diagnostic for such literals.
     pub fn value(&self) -> Result<u64, ParseIntError> {
         self.0.text().parse()
     }
+
+    /// Get the numeric value of the literal as an `i128`, which can hold values
+    /// beyond the range of `u64`.
+    ///
+    /// # Errors
+    /// This function may return an error if the value cannot be parsed into an `i128`.
+    pub fn value_i128(&self) -> Result<i128, ParseIntError> {
+        self.0.text().parse()
+    }
+
+    /// The decimal digits of the literal without leading zeros, which represent the
+    /// value exactly regardless of its size.
+    pub fn digits(&self) -> &str {
+        let digits = self.0.text().trim_start_matches('0');
+        if digits.is_empty() {
+            "0"
+        } else {
+            digits
+        }
+    }
 }
 
 impl Spanned for LitInt {
@@ -137,3 +225,102 @@ impl Spanned for LitInt {
         self.0.text_range()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{ast::Expr, parse};
+
+    use super::{Lit, Literal};
+
+    #[track_caller]
+    fn lit(input: &str) -> Lit {
+        let Some(Expr::Clause(clause)) = parse(input).to_expr() else {
+            panic!("Expression was not clause");
+        };
+
+        clause
+            .operand()
+            .unwrap()
+            .literal()
+            .unwrap()
+            .value()
+            .unwrap()
+    }
+
+    /// Strings have no escapes, so backslashes are ordinary characters, including
+    /// before the closing quote.
+    #[test]
+    fn str_keeps_backslashes() {
+        for (input, expected) in [
+            (r"p:'\Device\*'", r"\Device\*"),
+            (r"p:'C:\temp'", r"C:\temp"),
+            (r"p:'C:\\temp'", r"C:\\temp"),
+            (r"p:'C:\'", r"C:\"),
+            (r"p:['C:\']", r"C:\"),
+        ] {
+            assert!(parse(input).error_messages().is_empty(), "{input}");
+
+            let Lit::Str(s) = lit(input) else {
+                panic!("Expected string");
+            };
+
+            assert_eq!(s.value(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn str_trailing_backslash_then_clause() {
+        let filter = parse(r"p:'C:\'+n:'x'").to_filter().unwrap();
+        assert_eq!(filter.predicates().count(), 2);
+    }
+
+    #[test]
+    fn from_string_round_trips() {
+        for value in ["", "plain", r"C:\", r"\\", "multi\nline", "ünïcödé *"] {
+            let Some(Lit::Str(s)) = Literal::from_string(value).unwrap().value() else {
+                panic!("Expected string literal for {value:?}");
+            };
+
+            assert_eq!(s.value(), value);
+        }
+    }
+
+    #[test]
+    fn from_string_rejects_quotes() {
+        for value in ["it's", "'", r"\'"] {
+            assert!(Literal::from_string(value).is_none(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn from_scalars() {
+        let Some(Lit::Int(i)) = Literal::from_u64(u64::MAX).value() else {
+            panic!("Expected integer literal");
+        };
+        assert_eq!(i.value(), Ok(u64::MAX));
+
+        let Some(Lit::Bool(b)) = Literal::from_bool(false).value() else {
+            panic!("Expected boolean literal");
+        };
+        assert!(!b.value());
+    }
+
+    #[test]
+    fn from_timestamp() {
+        let literal = Literal::from_timestamp(UNIX_EPOCH + Duration::from_secs(1_648_816_200));
+        assert_eq!(literal.to_string(), "'2022-04-01T12:30:00Z'");
+    }
+
+    #[test]
+    fn int_beyond_u64() {
+        let Lit::Int(i) = lit("a:00036893488147419103232") else {
+            panic!("Expected integer");
+        };
+
+        assert!(i.value().is_err());
+        assert_eq!(i.value_i128(), Ok(36_893_488_147_419_103_232));
+        assert_eq!(i.digits(), "36893488147419103232");
+    }
+}
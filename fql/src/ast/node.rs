@@ -0,0 +1,144 @@
+use std::fmt;
+
+use rowan::ast::AstNode;
+
+use crate::{
+    syntax::{Fql, SyntaxKind, SyntaxNode},
+    Spanned,
+};
+
+use super::{Clause, Expr, ExprBinary, ExprParen, Literal, Operand, Placeholder, Property};
+
+/// Any typed node in the syntax tree.
+///
+/// This is returned by position-based lookups such as [`Parse::node_at_offset`](crate::Parse::node_at_offset),
+/// where the kind of node found is not known in advance.
+#[derive(Debug, Clone)]
+pub enum Node {
+    Binary(ExprBinary),
+    Paren(ExprParen),
+    Clause(Clause),
+    Property(Property),
+    Operand(Operand),
+    Literal(Literal),
+    Placeholder(Placeholder),
+}
+
+impl Node {
+    /// The innermost typed node which contains this one, if any.
+    ///
+    /// Repeatedly calling this walks outwards through enclosing nodes, which is
+    /// useful for features such as "select enclosing expression".
+    pub fn parent(&self) -> Option<Node> {
+        self.syntax().ancestors().skip(1).find_map(Node::cast)
+    }
+
+    /// The node as an expression, if it is one.
+    pub fn as_expr(&self) -> Option<Expr> {
+        Expr::cast(self.syntax().clone())
+    }
+}
+
+impl AstNode for Node {
+    type Language = Fql;
+
+    fn can_cast(kind: SyntaxKind) -> bool {
+        Expr::can_cast(kind)
+            || Property::can_cast(kind)
+            || Operand::can_cast(kind)
+            || Literal::can_cast(kind)
+            || Placeholder::can_cast(kind)
+    }
+
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(match node.kind() {
+            SyntaxKind::Property => Self::Property(Property::cast(node)?),
+            SyntaxKind::Operand => Self::Operand(Operand::cast(node)?),
+            SyntaxKind::Literal => Self::Literal(Literal::cast(node)?),
+            SyntaxKind::Placeholder => Self::Placeholder(Placeholder::cast(node)?),
+            _ => match Expr::cast(node)? {
+                Expr::Binary(v) => Self::Binary(v),
+                Expr::Paren(v) => Self::Paren(v),
+                Expr::Clause(v) => Self::Clause(v),
+            },
+        })
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Node::Binary(v) => v.syntax(),
+            Node::Paren(v) => v.syntax(),
+            Node::Clause(v) => v.syntax(),
+            Node::Property(v) => v.syntax(),
+            Node::Operand(v) => v.syntax(),
+            Node::Literal(v) => v.syntax(),
+            Node::Placeholder(v) => v.syntax(),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.syntax().fmt(f)
+    }
+}
+
+impl Spanned for Node {
+    fn span(&self) -> rowan::TextRange {
+        self.syntax().text_range()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Spanned, TextRange};
+
+    use super::Node;
+
+    #[track_caller]
+    fn node_at(input: &str, offset: u32) -> Node {
+        parse(input).node_at_offset(offset.into()).unwrap()
+    }
+
+    #[test]
+    fn innermost_node() {
+        assert!(matches!(node_at("host.name:'x'", 2), Node::Property(_)));
+        assert!(matches!(node_at("host.name:'x'", 11), Node::Literal(_)));
+        assert!(matches!(node_at("a:1+b:2", 3), Node::Literal(_)));
+        assert!(matches!(node_at("(a:1),(b:2)", 5), Node::Binary(_)));
+        assert!(matches!(node_at("a:1+b:$v", 7), Node::Placeholder(_)));
+    }
+
+    #[test]
+    fn prefers_identifiers_and_literals_at_boundaries() {
+        let parse = parse("a:1+b:2");
+        assert_eq!(parse.token_at_offset(1.into()).unwrap().text(), "a");
+        assert_eq!(parse.token_at_offset(4.into()).unwrap().text(), "b");
+        assert_eq!(parse.token_at_offset(7.into()).unwrap().text(), "2");
+        assert!(crate::parse("").token_at_offset(0.into()).is_none());
+    }
+
+    #[test]
+    fn inside_error_region() {
+        // The clause is missing its operand, but the property is still found.
+        assert!(matches!(node_at("a:1+hostname:", 6), Node::Property(_)));
+        assert!(matches!(node_at("a:1+hostname:", 13), Node::Clause(_)));
+    }
+
+    #[test]
+    fn covering_element() {
+        let parse = parse("a:1+(b:2,c:3)");
+        let covering = parse
+            .covering_element(TextRange::new(6.into(), 10.into()))
+            .unwrap();
+        assert!(matches!(covering, Node::Binary(_)));
+        assert_eq!(covering.to_string(), "b:2,c:3");
+
+        let parent = covering.parent().unwrap();
+        assert!(matches!(parent, Node::Paren(_)));
+        assert_eq!(parent.span(), TextRange::new(4.into(), 13.into()));
+    }
+}
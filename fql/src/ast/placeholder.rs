@@ -0,0 +1,16 @@
+use crate::{
+    ast_node,
+    syntax::{SyntaxElement, SyntaxKind, SyntaxToken},
+};
+
+ast_node!(Placeholder);
+
+impl Placeholder {
+    /// The identifier token naming the placeholder, without the leading `$`.
+    pub fn name(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|t| t.kind() == SyntaxKind::Ident)
+    }
+}
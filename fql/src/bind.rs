@@ -0,0 +1,287 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use rowan::{ast::AstNode, NodeOrToken};
+
+use crate::{
+    ast::{Clause, ClauseOperator, Operand, Placeholder},
+    quote_str,
+    syntax::SyntaxKind,
+    Parse, Spanned, TextRange, Value,
+};
+
+impl Parse {
+    /// Replace every `$name` placeholder in the filter with the literal for the
+    /// parameter of the same name, returning the resulting filter text.
+    ///
+    /// Values are written as FQL literals, with string values quoted, so a parameter
+    /// can never change the structure of the filter.
+    ///
+    /// # Errors
+    /// Binding fails if the filter has syntax errors, if a placeholder has no
+    /// parameter, if a parameter has no placeholder, or if a parameter's type can't
+    /// be used where its placeholder appears. Placeholders in brackets or after `~`
+    /// and `!~` need strings, and placeholders after a range operator need integers
+    /// or strings. FQL strings have no escapes, so binding also fails if a string
+    /// parameter contains a single quote.
+    ///
+    /// # Example
+    /// ```rust
+    /// let parse = fql::parse("hostname:$name+last_seen:>$since");
+    /// let bound = parse
+    ///     .bind([("name", "Brien's PC".into()), ("since", 1_650_000_000.into())]);
+    /// assert!(bound.is_err());
+    ///
+    /// let bound = parse
+    ///     .bind([("name", r"C:\temp".into()), ("since", 1_650_000_000.into())])
+    ///     .unwrap();
+    /// assert_eq!(bound, r"hostname:'C:\temp'+last_seen:>1650000000");
+    /// ```
+    pub fn bind<K: Into<String>>(
+        &self,
+        params: impl IntoIterator<Item = (K, Value)>,
+    ) -> Result<String, BindError> {
+        if let Some(error) = self.diagnostics().next() {
+            return Err(BindError::Syntax { span: error.span() });
+        }
+
+        let params = params
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect::<BTreeMap<String, Value>>();
+        let mut used = vec![false; params.len()];
+        let mut output = String::new();
+
+        for element in self.syntax().descendants_with_tokens() {
+            match element {
+                NodeOrToken::Node(node) => {
+                    let Some(placeholder) = Placeholder::cast(node) else {
+                        continue;
+                    };
+
+                    let name = placeholder
+                        .name()
+                        .map(|t| t.text().to_string())
+                        .unwrap_or_default();
+                    let Some(index) = params.keys().position(|key| *key == name) else {
+                        return Err(BindError::Missing {
+                            name,
+                            span: placeholder.span(),
+                        });
+                    };
+
+                    let value = &params[&name];
+                    if let Some(expected) = expected_type(&placeholder) {
+                        if !expected.accepts(value) {
+                            return Err(BindError::Mistyped {
+                                name,
+                                expected,
+                                span: placeholder.span(),
+                            });
+                        }
+                    }
+
+                    let literal = match value {
+                        Value::Str(s) => quote_str(s).ok_or_else(|| BindError::Unquotable {
+                            name: name.clone(),
+                            span: placeholder.span(),
+                        })?,
+                        value => value.to_string(),
+                    };
+
+                    used[index] = true;
+                    output.push_str(&literal);
+                }
+                NodeOrToken::Token(token) => {
+                    let in_placeholder = token
+                        .parent()
+                        .is_some_and(|parent| parent.kind() == SyntaxKind::Placeholder);
+                    if !in_placeholder {
+                        output.push_str(token.text());
+                    }
+                }
+            }
+        }
+
+        if let Some((name, _)) = params.keys().zip(&used).find(|(_, used)| !**used) {
+            return Err(BindError::Unused { name: name.clone() });
+        }
+
+        Ok(output)
+    }
+}
+
+/// Work out which parameter types are allowed where `placeholder` appears.
+fn expected_type(placeholder: &Placeholder) -> Option<ExpectedType> {
+    let operand = placeholder.syntax().parent().and_then(Operand::cast)?;
+    if operand.is_exact() {
+        return Some(ExpectedType::String);
+    }
+
+    let clause = operand.syntax().parent().and_then(Clause::cast)?;
+    match clause.operator_kind() {
+        ClauseOperator::Contains | ClauseOperator::NotContains => Some(ExpectedType::String),
+        op if op.is_range() => Some(ExpectedType::IntegerOrString),
+        _ => None,
+    }
+}
+
+/// The kinds of value a placeholder accepts, based on where it appears.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedType {
+    String,
+    IntegerOrString,
+}
+
+impl ExpectedType {
+    fn accepts(self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (_, Value::Str(_)) | (Self::IntegerOrString, Value::Int(_))
+        )
+    }
+}
+
+impl fmt::Display for ExpectedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::IntegerOrString => "integer or string",
+        })
+    }
+}
+
+/// An error from [`Parse::bind`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindError {
+    /// The filter has a syntax error at `span`.
+    Syntax { span: TextRange },
+    /// The placeholder at `span` has no matching parameter.
+    Missing { name: String, span: TextRange },
+    /// A parameter was provided which no placeholder uses.
+    Unused { name: String },
+    /// The parameter's value can't be used at the placeholder at `span`.
+    Mistyped {
+        name: String,
+        expected: ExpectedType,
+        span: TextRange,
+    },
+    /// The parameter is a string containing a single quote, which an FQL string
+    /// literal can't hold.
+    Unquotable { name: String, span: TextRange },
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindError::Syntax { span } => write!(
+                f,
+                "Filter has a syntax error at {}..{}",
+                u32::from(span.start()),
+                u32::from(span.end())
+            ),
+            BindError::Missing { name, .. } => write!(f, "No parameter for placeholder ${name}"),
+            BindError::Unused { name } => {
+                write!(f, "Parameter {name} is not used by any placeholder")
+            }
+            BindError::Mistyped { name, expected, .. } => {
+                write!(f, "Parameter {name} must be a {expected}")
+            }
+            BindError::Unquotable { name, .. } => {
+                write!(
+                    f,
+                    "Parameter {name} contains a single quote, which FQL strings can't hold"
+                )
+            }
+        }
+    }
+}
+
+impl Error for BindError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Value};
+
+    use super::{BindError, ExpectedType};
+
+    #[test]
+    fn binds_literals() {
+        assert_eq!(
+            parse("a:$x+b:[$y],c:!$z")
+                .bind([
+                    ("x", Value::from("it is")),
+                    ("y", Value::from(r"C:\")),
+                    ("z", Value::from(true)),
+                ])
+                .unwrap(),
+            r"a:'it is'+b:['C:\'],c:!true"
+        );
+    }
+
+    #[test]
+    fn bound_backslash_keeps_structure() {
+        let bound = parse("a:$x+b:$y")
+            .bind([("x", Value::from(r"C:\")), ("y", Value::from("x"))])
+            .unwrap();
+        let filter = parse(&bound).to_filter().unwrap();
+        assert_eq!(filter.predicates().count(), 2);
+    }
+
+    #[test]
+    fn injection_rejected() {
+        assert!(matches!(
+            parse("hostname:$name").bind([("name", Value::from("x'+cid:'*"))]),
+            Err(BindError::Unquotable { name, .. }) if name == "name"
+        ));
+    }
+
+    #[test]
+    fn repeated_placeholder() {
+        assert_eq!(
+            parse("a:$x,b:$x").bind([("x", Value::from(1))]).unwrap(),
+            "a:1,b:1"
+        );
+    }
+
+    #[test]
+    fn missing() {
+        assert!(matches!(
+            parse("a:$x+b:$y").bind([("x", Value::from(1))]),
+            Err(BindError::Missing { name, .. }) if name == "y"
+        ));
+    }
+
+    #[test]
+    fn unused() {
+        assert_eq!(
+            parse("a:$x").bind([("x", Value::from(1)), ("y", Value::from(2))]),
+            Err(BindError::Unused { name: "y".into() })
+        );
+    }
+
+    #[test]
+    fn mistyped() {
+        assert!(matches!(
+            parse("a:~$x").bind([("x", Value::from(1))]),
+            Err(BindError::Mistyped {
+                expected: ExpectedType::String,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("a:>$x").bind([("x", Value::from(true))]),
+            Err(BindError::Mistyped {
+                expected: ExpectedType::IntegerOrString,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn syntax_error() {
+        assert!(matches!(
+            parse("a:$").bind(Vec::<(String, Value)>::new()),
+            Err(BindError::Syntax { .. })
+        ));
+    }
+}
@@ -0,0 +1,275 @@
+//! Combining filters with `+` and `,` without changing their meaning.
+//!
+//! Joining filter text directly is wrong when precedence differs: `+` binds more
+//! tightly than `,`, so `format!("{a}+{b}")` with `a` as `x:1,y:2` produces a filter
+//! which matches every record with `y:2`, whatever `b` is. The functions here add
+//! parentheses where precedence requires them, and nowhere else.
+//!
+//! The result is built from the inputs' syntax trees rather than by parsing the
+//! joined text again, so each input keeps the structure it was parsed with. The
+//! diagnostics of the inputs are carried over to the result, with ranges in the
+//! combined text.
+//!
+//! An empty filter matches every record, so it is dropped from [`and`], and makes
+//! the result of [`or`] empty. No FQL filter matches nothing, so [`or`] with no
+//! inputs is an error.
+
+use std::{error::Error, fmt};
+
+use rowan::{GreenNode, GreenToken, Language, NodeOrToken, TextSize};
+
+use crate::{
+    ast::{BooleanContext, Expr},
+    syntax::{Fql, SyntaxKind},
+    Parse, ParseError,
+};
+
+/// Combine `parses` so the result matches records which every input matches.
+///
+/// # Example
+/// ```rust
+/// let parses = ["a:1,b:2", "", "c:3+d:4"].map(fql::parse);
+/// let composed = fql::compose::and(&parses);
+/// assert_eq!(composed.syntax_text(), "(a:1,b:2)+c:3+d:4");
+/// ```
+pub fn and(parses: &[Parse]) -> Parse {
+    let mut composer = Composer::new(SyntaxKind::Plus);
+    for parse in parses {
+        let wrap = matches!(
+            parse.to_expr(),
+            Some(Expr::Binary(binary)) if binary.context() == BooleanContext::Or
+        );
+
+        composer.push(parse, wrap);
+    }
+
+    composer.finish()
+}
+
+/// Combine `parses` so the result matches records which any input matches.
+///
+/// No parentheses are needed, since `,` binds less tightly than `+`. If any input
+/// is empty the result is empty, since that input already matches every record.
+///
+/// # Example
+/// ```rust
+/// let parses = ["a:1+b:2", "(c:3,d:4)"].map(fql::parse);
+/// let composed = fql::compose::or(&parses).unwrap();
+/// assert_eq!(composed.syntax_text(), "a:1+b:2,(c:3,d:4)");
+/// ```
+///
+/// # Errors
+/// Fails if there are no inputs.
+pub fn or(parses: &[Parse]) -> Result<Parse, ComposeError> {
+    if parses.is_empty() {
+        return Err(ComposeError::NoInputs);
+    }
+
+    let mut composer = Composer::new(SyntaxKind::Comma);
+    for parse in parses {
+        if parse.syntax_text().is_empty() {
+            return Ok(crate::parse(""));
+        }
+
+        composer.push(parse, false);
+    }
+
+    Ok(composer.finish())
+}
+
+/// Builds the tree of a combined filter, joining each non-empty input to the ones
+/// before it with a binary expression.
+struct Composer {
+    op: SyntaxKind,
+    children: Vec<NodeOrToken<GreenNode, GreenToken>>,
+    len: TextSize,
+    errors: Vec<ParseError>,
+}
+
+impl Composer {
+    fn new(op: SyntaxKind) -> Self {
+        Self {
+            op,
+            children: vec![],
+            len: 0.into(),
+            errors: vec![],
+        }
+    }
+
+    fn push(&mut self, parse: &Parse, wrap: bool) {
+        let root = parse.syntax();
+        if root.text_range().is_empty() {
+            return;
+        }
+
+        let mut items = vec![];
+        let joined = !self.children.is_empty();
+        if joined {
+            items.push(self.token(self.op));
+        }
+
+        let mut body = vec![];
+        if wrap {
+            body.push(self.token(SyntaxKind::LParen));
+        }
+
+        self.errors
+            .extend(parse.diagnostics().map(|e| e.clone().shift(self.len)));
+        self.len += root.text_range().len();
+        body.extend(root.children_with_tokens().map(|element| match element {
+            NodeOrToken::Node(node) => NodeOrToken::Node(node.green().into_owned()),
+            NodeOrToken::Token(token) => NodeOrToken::Token(token.green().to_owned()),
+        }));
+
+        if wrap {
+            body.push(self.token(SyntaxKind::RParen));
+            items.push(NodeOrToken::Node(GreenNode::new(
+                Fql::kind_to_raw(SyntaxKind::ParenExpr),
+                body,
+            )));
+        } else {
+            items.extend(body);
+        }
+
+        if joined {
+            let mut children = std::mem::take(&mut self.children);
+            children.extend(items);
+            self.children = vec![NodeOrToken::Node(GreenNode::new(
+                Fql::kind_to_raw(SyntaxKind::InfixExpr),
+                children,
+            ))];
+        } else {
+            self.children = items;
+        }
+    }
+
+    /// Create a punctuation token, counting its length.
+    fn token(&mut self, kind: SyntaxKind) -> NodeOrToken<GreenNode, GreenToken> {
+        let text = match kind {
+            SyntaxKind::Plus => "+",
+            SyntaxKind::Comma => ",",
+            SyntaxKind::LParen => "(",
+            SyntaxKind::RParen => ")",
+            _ => unreachable!("{kind:?} is not punctuation"),
+        };
+
+        self.len += TextSize::of(text);
+        NodeOrToken::Token(GreenToken::new(Fql::kind_to_raw(kind), text))
+    }
+
+    fn finish(self) -> Parse {
+        Parse::from_parts(
+            GreenNode::new(Fql::kind_to_raw(SyntaxKind::Root), self.children),
+            self.errors,
+        )
+    }
+}
+
+/// An error from [`or`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposeError {
+    /// [`or`] was given no inputs, so the result would match nothing, which can't be
+    /// written in FQL.
+    NoInputs,
+}
+
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComposeError::NoInputs => write!(f, "No filters to combine"),
+        }
+    }
+}
+
+impl Error for ComposeError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Expr, parse, semantics::equivalent, Filter, Parse, Spanned, TextRange};
+
+    use super::{and, or, ComposeError};
+
+    /// Check that `parse` has no diagnostics and the tree that parsing its text
+    /// would produce, and get its text.
+    #[track_caller]
+    fn text(parse: Parse) -> String {
+        assert!(
+            parse.error_messages().is_empty(),
+            "{:?}",
+            parse.error_messages()
+        );
+        let text = parse.syntax_text();
+        assert_eq!(parse.debug_tree(), crate::parse(&text).debug_tree());
+        text
+    }
+
+    #[test]
+    fn minimal_parens() {
+        assert_eq!(
+            text(and(&[parse("a:1"), parse("b:2,c:3")])),
+            "a:1+(b:2,c:3)"
+        );
+        assert_eq!(text(and(&[parse("a:1+b:2"), parse("c:3")])), "a:1+b:2+c:3");
+        assert_eq!(
+            text(and(&[parse("(a:1,b:2)"), parse("c:3")])),
+            "(a:1,b:2)+c:3"
+        );
+        assert_eq!(
+            text(or(&[parse("a:1,b:2"), parse("c:3+d:4")]).unwrap()),
+            "a:1,b:2,c:3+d:4"
+        );
+    }
+
+    #[test]
+    fn empty_filters() {
+        assert_eq!(text(and(&[parse(""), parse("a:1"), parse("")])), "a:1");
+        assert_eq!(text(and(&[])), "");
+        assert_eq!(text(or(&[parse("a:1"), parse("")]).unwrap()), "");
+        assert!(matches!(or(&[]), Err(ComposeError::NoInputs)));
+    }
+
+    #[test]
+    fn preserves_meaning() {
+        let inputs = ["a:1,b:2", "c:3+d:4", "e:5,(f:6+g:7)"].map(parse);
+        let filters = inputs
+            .iter()
+            .map(|p| p.to_filter().unwrap())
+            .collect::<Vec<_>>();
+
+        let composed = and(&inputs).to_filter().unwrap();
+        assert!(equivalent(&composed, &Filter::And(filters.clone())).is_equivalent());
+
+        let composed = or(&inputs).unwrap().to_filter().unwrap();
+        assert!(equivalent(&composed, &Filter::Or(filters)).is_equivalent());
+    }
+
+    #[test]
+    fn trailing_backslash() {
+        let inputs = [parse(r"p:'C:\'"), parse("q:'x'")];
+        assert_eq!(text(and(&inputs)), r"p:'C:\'+q:'x'");
+        assert_eq!(text(or(&inputs).unwrap()), r"p:'C:\',q:'x'");
+    }
+
+    #[test]
+    fn propagates_diagnostics() {
+        let composed = and(&[parse("a:1,b:2"), parse("c:")]);
+        assert_eq!(composed.syntax_text(), "(a:1,b:2)+c:");
+        assert_eq!(
+            composed.error_messages(),
+            ["At 11..12, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', '$', boolean, string, or integer"]
+        );
+
+        // A stray `)` stays inside the input it came from, rather than closing the
+        // parenthesis it was wrapped in.
+        let composed = and(&[parse("a:1,b:2)"), parse("c:3")]);
+        assert_eq!(composed.syntax_text(), "(a:1,b:2))+c:3");
+        let errors = composed.diagnostics().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span(), TextRange::new(8.into(), 9.into()));
+        let Some(Expr::Binary(binary)) = composed.to_expr() else {
+            panic!("Expected a binary expression");
+        };
+        assert!(matches!(binary.lhs(), Some(Expr::Paren(_))));
+        assert_eq!(binary.rhs().unwrap().to_string(), "c:3");
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+
+use crate::{Spanned, TextRange, TextSize};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// The filter is valid, but probably does not do what was intended.
+    Warning,
+    /// The filter will be rejected or cannot match anything useful.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A problem found in well-formed input, such as a clause on an unknown property.
+///
+/// Syntax errors are reported separately as [`ParseError`](crate::ParseError)s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    range: TextRange,
+    fix: Option<Fix>,
+    rule: Option<&'static str>,
+}
+
+impl Diagnostic {
+    /// Create an error. The message should start with a lowercase letter.
+    pub fn error(message: impl Into<String>, range: TextRange) -> Self {
+        Self::new(Severity::Error, message, range)
+    }
+
+    /// Create a warning. The message should start with a lowercase letter.
+    pub fn warning(message: impl Into<String>, range: TextRange) -> Self {
+        Self::new(Severity::Warning, message, range)
+    }
+
+    /// Create a diagnostic. The message should start with a lowercase letter.
+    pub fn new(severity: Severity, message: impl Into<String>, range: TextRange) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            range,
+            fix: None,
+            rule: None,
+        }
+    }
+
+    /// Attach a quick fix which resolves the diagnostic.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Record the ID of the lint rule which produced the diagnostic.
+    pub fn with_rule(mut self, rule: &'static str) -> Self {
+        self.rule = Some(rule);
+        self
+    }
+
+    /// Move the diagnostic and its fix `offset` bytes later in the input.
+    pub(crate) fn shift(mut self, offset: TextSize) -> Self {
+        self.range += offset;
+        if let Some(fix) = &mut self.fix {
+            for edit in &mut fix.edits {
+                edit.range += offset;
+            }
+        }
+
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// The message, without range information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The ID of the lint rule which produced the diagnostic, such as `duplicate-clause`.
+    pub fn rule(&self) -> Option<&'static str> {
+        self.rule
+    }
+
+    /// A suggested change to the input which resolves the diagnostic.
+    pub fn fix(&self) -> Option<&Fix> {
+        self.fix.as_ref()
+    }
+}
+
+/// Display the diagnostic.
+///
+/// Like [`ParseError`](crate::ParseError), the default formatting will include range
+/// information, while the alternate form will omit that data.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let mut chars = self.message.chars();
+            if let Some(first) = chars.next() {
+                write!(f, "{}{}", first.to_uppercase(), chars.as_str())?;
+            }
+
+            Ok(())
+        } else {
+            write!(
+                f,
+                "At {}..{}, {}",
+                u32::from(self.range.start()),
+                u32::from(self.range.end()),
+                self.message
+            )
+        }
+    }
+}
+
+impl Spanned for Diagnostic {
+    fn span(&self) -> TextRange {
+        self.range
+    }
+}
+
+/// A change to the input which resolves a [`Diagnostic`], such as replacing a misspelled
+/// property name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    title: String,
+    edits: Vec<TextEdit>,
+}
+
+impl Fix {
+    /// Create a fix from a short description, such as "Replace with 'hostname'", and
+    /// the edits which make it. The edits must not overlap.
+    pub fn new(title: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: title.into(),
+            edits,
+        }
+    }
+
+    /// Create a fix which replaces the text at `range` with `replacement`.
+    pub fn replace(
+        title: impl Into<String>,
+        range: TextRange,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            title,
+            vec![TextEdit {
+                range,
+                replacement: replacement.into(),
+            }],
+        )
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn edits(&self) -> &[TextEdit] {
+        &self.edits
+    }
+
+    /// Apply the fix to the input it was computed from.
+    ///
+    /// Returns `None` if the fix doesn't fit `input`: an edit's range is out of
+    /// bounds or doesn't fall on character boundaries, or two edits overlap.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::{Fix, TextRange};
+    ///
+    /// let fix = Fix::replace("Replace with 'hostname'", TextRange::new(0.into(), 7.into()), "hostname");
+    /// assert_eq!(fix.apply("hostnme:'web'").as_deref(), Some("hostname:'web'"));
+    /// assert_eq!(fix.apply("host"), None);
+    /// ```
+    pub fn apply(&self, input: &str) -> Option<String> {
+        let mut edits = self.edits.iter().collect::<Vec<_>>();
+        edits.sort_by_key(|edit| edit.range.start());
+
+        let mut output = String::with_capacity(input.len());
+        let mut cursor = 0;
+        for edit in edits {
+            let start = usize::from(edit.range.start());
+            let end = usize::from(edit.range.end());
+
+            // `get` rejects ranges which are out of bounds, off a character boundary, or
+            // reversed, as the first is if this edit overlaps the previous one.
+            output.push_str(input.get(cursor..start)?);
+            input.get(start..end)?;
+            output.push_str(&edit.replacement);
+            cursor = end;
+        }
+
+        output.push_str(&input[cursor..]);
+        Some(output)
+    }
+}
+
+/// A replacement of one range of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub replacement: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextRange;
+
+    use super::{Fix, TextEdit};
+
+    fn edit(start: u32, end: u32, replacement: &str) -> TextEdit {
+        TextEdit {
+            range: TextRange::new(start.into(), end.into()),
+            replacement: replacement.into(),
+        }
+    }
+
+    #[test]
+    fn apply_sorts_edits() {
+        let fix = Fix::new("Test", vec![edit(4, 7, "c:3"), edit(0, 3, "a:1")]);
+        assert_eq!(fix.apply("x:1+y:2").as_deref(), Some("a:1+c:3"));
+
+        let fix = Fix::new("Test", vec![edit(0, 0, "("), edit(3, 3, ")")]);
+        assert_eq!(fix.apply("a:1").as_deref(), Some("(a:1)"));
+    }
+
+    #[test]
+    fn apply_rejects_invalid_edits() {
+        let fix = Fix::new("Test", vec![edit(0, 3, "a"), edit(2, 4, "b")]);
+        assert_eq!(fix.apply("x:1+y:2"), None);
+
+        let fix = Fix::replace("Test", TextRange::new(4.into(), 9.into()), "");
+        assert_eq!(fix.apply("x:1+y:2"), None);
+
+        // 'é' is two bytes long.
+        let fix = Fix::replace("Test", TextRange::new(3.into(), 4.into()), "e");
+        assert_eq!(fix.apply("a:'é'"), None);
+    }
+}
@@ -0,0 +1,372 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    ast::{Clause, ClauseOperator},
+    Filter, Parse, Predicate, PropertyPath, Spanned, TextRange,
+};
+
+/// Compare the clauses of two filters.
+///
+/// Clauses are aligned by property path. Clauses whose text is identical in both
+/// filters are considered unchanged, even if they moved. The remaining clauses for
+/// each property are paired in source order and reported as changed, and any
+/// clauses left over are reported as added or removed.
+///
+/// If the clauses found in both filters are combined differently, such as `+`
+/// becoming `,` or clauses moving between groups, a [`Change::Regrouped`] is
+/// reported first.
+///
+/// # Example
+/// ```rust
+/// let old = fql::parse("platform_name:'Windows'+last_seen:>100");
+/// let new = fql::parse("last_seen:>=100+platform_name:'Windows'+online:true");
+/// let diff = fql::diff(&old, &new);
+///
+/// assert_eq!(
+///     diff.to_string(),
+///     "~ last_seen:>100 -> last_seen:>=100\n+ online:true\n"
+/// );
+///
+/// let diff = fql::diff(&fql::parse("a:1+b:2"), &fql::parse("a:1,b:2"));
+/// assert_eq!(diff.to_string(), "~ grouping: a:1+b:2 -> a:1,b:2\n");
+/// ```
+pub fn diff(old: &Parse, new: &Parse) -> Diff {
+    let old_clauses = DiffClause::collect(old);
+    let new_clauses = DiffClause::collect(new);
+
+    // The index of the old clause each new clause is aligned with.
+    let mut aligned = vec![None; new_clauses.len()];
+    let mut unmatched_old = Vec::new();
+
+    // Align clauses which appear verbatim on both sides.
+    for (i, clause) in old_clauses.iter().enumerate() {
+        let pos = new_clauses
+            .iter()
+            .zip(&aligned)
+            .position(|(c, partner)| partner.is_none() && c.text == clause.text);
+        match pos {
+            Some(pos) => aligned[pos] = Some(i),
+            None => unmatched_old.push(i),
+        }
+    }
+
+    let mut added_by_property = BTreeMap::<&PropertyPath, Vec<usize>>::new();
+    for (j, clause) in new_clauses.iter().enumerate() {
+        if aligned[j].is_none() {
+            added_by_property
+                .entry(&clause.property)
+                .or_default()
+                .push(j);
+        }
+    }
+
+    let mut changes = Vec::new();
+    for i in unmatched_old {
+        let old = &old_clauses[i];
+        let paired = added_by_property
+            .get_mut(&old.property)
+            .filter(|candidates| !candidates.is_empty())
+            .map(|candidates| candidates.remove(0));
+
+        changes.push(match paired {
+            Some(j) => {
+                aligned[j] = Some(i);
+                Change::Changed {
+                    old: old.clone(),
+                    new: new_clauses[j].clone(),
+                }
+            }
+            None => Change::Removed(old.clone()),
+        });
+    }
+
+    let mut added = added_by_property
+        .into_values()
+        .flatten()
+        .map(|j| Change::Added(new_clauses[j].clone()))
+        .collect::<Vec<_>>();
+    added.sort_by_key(|change| change.after().map(|c| c.span.start()));
+    changes.extend(added);
+
+    if regrouped(old, new, old_clauses.len(), &aligned) {
+        let (old, new) = (old.to_expr(), new.to_expr());
+        changes.insert(
+            0,
+            Change::Regrouped {
+                old: old.as_ref().map(|e| e.to_string()).unwrap_or_default(),
+                old_span: old.map(|e| e.span()).unwrap_or_default(),
+                new: new.as_ref().map(|e| e.to_string()).unwrap_or_default(),
+                new_span: new.map(|e| e.span()).unwrap_or_default(),
+            },
+        );
+    }
+
+    Diff { changes }
+}
+
+/// Whether the clauses which are aligned between the two filters are combined with
+/// different operators or grouping.
+///
+/// `old` has `old_len` clauses, and `aligned` maps each clause of `new`, in source
+/// order, to the index of the old clause it is aligned with. Only the aligned clauses are compared, so adding or
+/// removing a clause isn't a regrouping by itself.
+fn regrouped(old: &Parse, new: &Parse, old_len: usize, aligned: &[Option<usize>]) -> bool {
+    let (Some(old), Some(new)) = (old.to_filter(), new.to_filter()) else {
+        return false;
+    };
+
+    // Clauses are lowered in source order, so the predicates line up with the
+    // collected clauses.
+    let predicates = old.predicates().cloned().collect::<Vec<_>>();
+    if predicates.len() != old_len || new.predicates().count() != aligned.len() {
+        return false;
+    }
+
+    let mut kept = vec![false; predicates.len()];
+    for &i in aligned.iter().flatten() {
+        kept[i] = true;
+    }
+
+    let mut leaves = kept
+        .iter()
+        .zip(&predicates)
+        .map(|(&keep, p)| keep.then(|| p.clone()));
+    let old = skeleton(old, &mut leaves);
+    let mut leaves = aligned.iter().map(|i| i.map(|i| predicates[i].clone()));
+    let new = skeleton(new, &mut leaves);
+
+    old.map(Filter::normalize) != new.map(Filter::normalize)
+}
+
+/// Rebuild `filter` with each clause replaced by the next item of `leaves`.
+///
+/// Clauses whose item is `None` are left out, along with groups which are left empty.
+fn skeleton(filter: Filter, leaves: &mut dyn Iterator<Item = Option<Predicate>>) -> Option<Filter> {
+    let mut rebuild = |children: Vec<Filter>| {
+        let children = children
+            .into_iter()
+            .filter_map(|child| skeleton(child, leaves))
+            .collect::<Vec<_>>();
+        (!children.is_empty()).then_some(children)
+    };
+
+    match filter {
+        Filter::Clause(_) => leaves.next().flatten().map(Filter::Clause),
+        Filter::And(children) => rebuild(children).map(Filter::And),
+        Filter::Or(children) => rebuild(children).map(Filter::Or),
+        Filter::True | Filter::False => Some(filter),
+    }
+}
+
+/// The differences between two filters, produced by [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// The changes, starting with any regrouping, then removed and changed clauses in
+    /// the order they appear in the old filter, followed by added clauses in the order
+    /// they appear in the new filter.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Whether the two filters contain the same clauses, combined in the same way.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Display the diff with one change per line, prefixed by `-`, `+`, or `~`.
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single difference between two filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A clause which only exists in the new filter.
+    Added(DiffClause),
+    /// A clause which only exists in the old filter.
+    Removed(DiffClause),
+    /// A clause on the same property whose operator or operand changed.
+    Changed { old: DiffClause, new: DiffClause },
+    /// The clauses found in both filters are combined with different operators or
+    /// grouping, such as `a:1+b:2` becoming `a:1,b:2`. This holds the text of both
+    /// filters, and the range of each in the input it was parsed from.
+    Regrouped {
+        old: String,
+        old_span: TextRange,
+        new: String,
+        new_span: TextRange,
+    },
+}
+
+impl Change {
+    /// The property path of the changed clause, or `None` for a regrouping.
+    pub fn property(&self) -> Option<&PropertyPath> {
+        match self {
+            Change::Added(clause) | Change::Removed(clause) => Some(&clause.property),
+            Change::Changed { old, .. } => Some(&old.property),
+            Change::Regrouped { .. } => None,
+        }
+    }
+
+    /// The clause as it appeared in the old filter, if it existed there.
+    pub fn before(&self) -> Option<&DiffClause> {
+        match self {
+            Change::Removed(old) | Change::Changed { old, .. } => Some(old),
+            Change::Added(_) | Change::Regrouped { .. } => None,
+        }
+    }
+
+    /// The clause as it appears in the new filter, if it exists there.
+    pub fn after(&self) -> Option<&DiffClause> {
+        match self {
+            Change::Added(new) | Change::Changed { new, .. } => Some(new),
+            Change::Removed(_) | Change::Regrouped { .. } => None,
+        }
+    }
+
+    /// Whether a changed clause uses a different operator, such as `>` becoming `>=`.
+    pub fn operator_changed(&self) -> bool {
+        matches!(self, Change::Changed { old, new } if old.operator != new.operator)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(clause) => write!(f, "+ {}", clause.text),
+            Change::Removed(clause) => write!(f, "- {}", clause.text),
+            Change::Changed { old, new } => write!(f, "~ {} -> {}", old.text, new.text),
+            Change::Regrouped { old, new, .. } => write!(f, "~ grouping: {old} -> {new}"),
+        }
+    }
+}
+
+/// A clause referenced by a [`Change`], with its location in the filter it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffClause {
+    pub property: PropertyPath,
+    pub operator: ClauseOperator,
+    /// The source text of the whole clause.
+    pub text: String,
+    /// The range of the clause in the input it was parsed from.
+    pub span: TextRange,
+}
+
+impl DiffClause {
+    /// Collect the clauses of a parse result in source order.
+    fn collect(parse: &Parse) -> Vec<Self> {
+        let mut clauses = parse
+            .to_expr()
+            .map(|expr| expr.clauses().filter_map(Self::new).collect::<Vec<_>>())
+            .unwrap_or_default();
+        clauses.sort_by_key(|c| c.span.start());
+        clauses
+    }
+
+    fn new(clause: Clause) -> Option<Self> {
+        Some(Self {
+            property: clause.property()?.into(),
+            operator: clause.operator_kind(),
+            text: clause.to_string(),
+            span: clause.span(),
+        })
+    }
+}
+
+impl Spanned for DiffClause {
+    fn span(&self) -> TextRange {
+        self.span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, TextRange};
+
+    use super::{diff, Change};
+
+    #[test]
+    fn unchanged_when_reordered() {
+        assert!(diff(&parse("a:1+(b:2,c:3)"), &parse("(c:3,b:2)+a:1")).is_empty());
+    }
+
+    #[test]
+    fn operator_change_has_spans() {
+        let result = diff(&parse("a:1+b:>5"), &parse("b:>=5+a:1"));
+        let [change] = result.changes() else {
+            panic!("Expected one change, got {result:?}");
+        };
+
+        assert!(change.operator_changed());
+        assert_eq!(change.property().unwrap().to_string(), "b");
+        assert_eq!(
+            change.before().unwrap().span,
+            TextRange::new(4.into(), 8.into())
+        );
+        assert_eq!(
+            change.after().unwrap().span,
+            TextRange::new(0.into(), 5.into())
+        );
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let result = diff(&parse("a:1+b:2+b:3"), &parse("b:4+c:5"));
+        assert_eq!(result.to_string(), "- a:1\n~ b:2 -> b:4\n- b:3\n+ c:5\n");
+        assert!(matches!(result.changes()[0], Change::Removed(_)));
+    }
+
+    #[test]
+    fn operator_and_grouping_changes() {
+        let result = diff(&parse("a:1+b:2"), &parse("a:1,b:2"));
+        assert_eq!(
+            result.changes(),
+            [Change::Regrouped {
+                old: "a:1+b:2".into(),
+                old_span: TextRange::new(0.into(), 7.into()),
+                new: "a:1,b:2".into(),
+                new_span: TextRange::new(0.into(), 7.into()),
+            }]
+        );
+        assert_eq!(result.changes()[0].property(), None);
+
+        let result = diff(&parse("(a:1,b:2)+(c:3,d:4)"), &parse("(a:1,c:3)+(b:2,d:4)"));
+        assert!(matches!(result.changes(), [Change::Regrouped { .. }]));
+
+        // The changed clause is compared in place of the old one.
+        let result = diff(&parse("a:1+(b:2,c:3)"), &parse("a:1,(b:5+c:3),d:4"));
+        assert_eq!(
+            result.to_string(),
+            "~ grouping: a:1+(b:2,c:3) -> a:1,(b:5+c:3),d:4\n~ b:2 -> b:5\n+ d:4\n"
+        );
+        let Change::Regrouped {
+            old_span, new_span, ..
+        } = &result.changes()[0]
+        else {
+            panic!("Expected a regrouping, got {result:?}");
+        };
+        assert_eq!(*old_span, TextRange::new(0.into(), 13.into()));
+        assert_eq!(*new_span, TextRange::new(0.into(), 17.into()));
+
+        // Adding or removing clauses doesn't regroup the others.
+        assert_eq!(
+            diff(&parse("a:1+b:2"), &parse("(a:1+b:2),c:3")).to_string(),
+            "+ c:3\n"
+        );
+        assert_eq!(
+            diff(&parse("a:1+b:2+a:1"), &parse("b:2+a:1")).to_string(),
+            "- a:1\n"
+        );
+    }
+}
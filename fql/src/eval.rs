@@ -0,0 +1,389 @@
+//! Applying filters to records in memory.
+//!
+//! A [`Filter`] can be evaluated against any type which implements [`Record`] with
+//! [`Filter::eval`], without sending it to a data source. `Record` is implemented for
+//! `HashMap<String, _>`, for `serde_json::Value` with the `serde_json` feature, and
+//! can be derived for structs with the `derive` feature.
+//!
+//! A property path is resolved through nested fields, so `device.os` refers to the
+//! `os` field of the `device` field. Arrays are searched element by element, both
+//! for the final value and for records along the path, so a clause such as `tags:'x'`
+//! matches if any element matches. Negated clauses, such as `tags:!'x'`, match if no
+//! element matches the positive form. A missing property matches only negated
+//! clauses, as in [`semantics`].
+
+use std::{error::Error, fmt};
+
+use crate::{ast::ClauseOperator, semantics, Filter, Predicate, PropertyPath, Value};
+
+mod compile;
+mod record;
+
+pub use self::compile::CompiledFilter;
+pub use self::record::{Field, FieldValue, Record};
+
+#[cfg(feature = "derive")]
+pub use fql_derive::Record;
+
+impl Filter {
+    /// Check whether `record` matches the filter.
+    ///
+    /// `And` and `Or` stop at the first child which decides the result, so a type
+    /// mismatch in a later child is only reported if that child is evaluated.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// let record = HashMap::from([
+    ///     ("hostname".to_string(), vec!["web-01"]),
+    ///     ("tags".to_string(), vec!["prod", "dmz"]),
+    /// ]);
+    ///
+    /// let filter = fql::parse("hostname:'WEB*'+tags:['dmz']").to_filter().unwrap();
+    /// assert!(filter.eval(&record).unwrap());
+    ///
+    /// let error = fql::parse("hostname:1").to_filter().unwrap().eval(&record).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Property 'hostname' is a string, but the filter expects an integer"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Evaluation fails if a value in the record can't be compared to the operand of
+    /// a clause, such as a string property compared to an integer.
+    pub fn eval<R: Record + ?Sized>(&self, record: &R) -> Result<bool, EvalError> {
+        match self {
+            Filter::And(children) => {
+                for child in children {
+                    if !child.eval(record)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            Filter::Or(children) => {
+                for child in children {
+                    if child.eval(record)? {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
+            }
+            Filter::Clause(predicate) => eval_clause(predicate, &record.get(&predicate.property)),
+            Filter::True => Ok(true),
+            Filter::False => Ok(false),
+        }
+    }
+}
+
+fn eval_clause(predicate: &Predicate, value: &FieldValue<'_>) -> Result<bool, EvalError> {
+    // A negated clause must hold for every value, since it matches when no value
+    // matches the positive form. A missing value has no values, so only matches
+    // negated clauses.
+    let negated = predicate.operator.is_negated();
+    let found = any_value(value, &mut |value| {
+        Ok(eval_value(predicate, value)? != negated)
+    })?;
+    Ok(found != negated)
+}
+
+/// Whether `f` returns true for any value in `value`, flattening arrays and skipping
+/// missing values.
+fn any_value(
+    value: &FieldValue<'_>,
+    f: &mut dyn FnMut(&FieldValue<'_>) -> Result<bool, EvalError>,
+) -> Result<bool, EvalError> {
+    match value {
+        FieldValue::Missing => Ok(false),
+        FieldValue::Array(elements) => {
+            for element in elements {
+                if any_value(element, f)? {
+                    return Ok(true);
+                }
+            }
+
+            Ok(false)
+        }
+        value => f(value),
+    }
+}
+
+/// Evaluate the clause against a single value from the record.
+fn eval_value(predicate: &Predicate, value: &FieldValue<'_>) -> Result<bool, EvalError> {
+    let value = match (&predicate.value, value) {
+        (Value::Str(_), FieldValue::Str(s)) => Value::Str(s.to_string()),
+        (Value::Bool(_), FieldValue::Bool(b)) => Value::Bool(*b),
+        (Value::Int(_), FieldValue::Int(n)) => Value::Int(*n),
+        (Value::Int(expected), FieldValue::Number(n)) => {
+            let positive = compare_float(predicate.operator, *n, *expected);
+            return Ok(positive != predicate.operator.is_negated());
+        }
+        (_, value) => {
+            return Err(EvalError::TypeMismatch {
+                property: predicate.property.clone(),
+                expected: ValueKind::of(&predicate.value),
+                found: ValueKind::of_field(value),
+            })
+        }
+    };
+
+    Ok(semantics::matches(predicate, Some(&value)))
+}
+
+/// Compare a negative or fractional number to an integer operand, ignoring whether
+/// `operator` is negated.
+fn compare_float(operator: ClauseOperator, actual: f64, expected: u64) -> bool {
+    let expected = expected as f64;
+    match operator {
+        ClauseOperator::Eq | ClauseOperator::Ne => actual == expected,
+        ClauseOperator::Gt => actual > expected,
+        ClauseOperator::Ge => actual >= expected,
+        ClauseOperator::Lt => actual < expected,
+        ClauseOperator::Le => actual <= expected,
+        ClauseOperator::Contains | ClauseOperator::NotContains => false,
+    }
+}
+
+/// The kind of a value in a filter or a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Integer,
+    /// A number which isn't a non-negative integer. This only appears in records.
+    Number,
+    Bool,
+    Object,
+}
+
+impl ValueKind {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Str(_) => Self::String,
+            Value::Int(_) => Self::Integer,
+            Value::Bool(_) => Self::Bool,
+        }
+    }
+
+    fn of_field(value: &FieldValue<'_>) -> Self {
+        match value {
+            FieldValue::Str(_) => Self::String,
+            FieldValue::Int(_) => Self::Integer,
+            FieldValue::Number(_) => Self::Number,
+            FieldValue::Bool(_) => Self::Bool,
+            // Arrays are flattened and missing values are skipped, so neither is ever
+            // compared to an operand.
+            FieldValue::Object | FieldValue::Array(_) | FieldValue::Missing => Self::Object,
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "a string",
+            Self::Integer => "an integer",
+            Self::Number => "a number",
+            Self::Bool => "a boolean",
+            Self::Object => "an object",
+        })
+    }
+}
+
+/// An error from [`Filter::eval`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// The record has a value of kind `found` for `property`, which can't be compared
+    /// to the clause's operand of kind `expected`.
+    TypeMismatch {
+        property: PropertyPath,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::TypeMismatch {
+                property,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Property '{property}' is {found}, but the filter expects {expected}"
+            ),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{parse, Filter};
+
+    use super::{EvalError, Field, Record, ValueKind};
+
+    #[track_caller]
+    fn eval(input: &str, record: &dyn Record) -> Result<bool, EvalError> {
+        parse(input).to_filter().unwrap().eval(record)
+    }
+
+    /// The same record built from maps, and as JSON if that feature is enabled.
+    fn records() -> Vec<Box<dyn Record>> {
+        #[allow(unused_mut)]
+        let mut records: Vec<Box<dyn Record>> = vec![Box::new(map_record())];
+        #[cfg(feature = "serde_json")]
+        records.push(Box::new(json_record()));
+        records
+    }
+
+    fn fields<const N: usize>(
+        entries: [(&str, Box<dyn Field>); N],
+    ) -> HashMap<String, Box<dyn Field>> {
+        entries
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    fn map_record() -> HashMap<String, Box<dyn Field>> {
+        fields([
+            ("hostname", Box::new("Web-01")),
+            ("online", Box::new(true)),
+            ("risk", Box::new(72_u64)),
+            ("score", Box::new(-1.5_f64)),
+            ("empty", Box::new(None::<u8>)),
+            (
+                "device",
+                Box::new(fields([
+                    (
+                        "os",
+                        Box::new(fields([
+                            ("name", Box::new("Windows")),
+                            ("build", Box::new(19045_u64)),
+                        ])),
+                    ),
+                    ("tags", Box::new(vec!["prod", "dmz"])),
+                ])),
+            ),
+            (
+                "interfaces",
+                Box::new(vec![
+                    HashMap::from([("ip".to_string(), "10.0.0.1")]),
+                    HashMap::from([("ip".to_string(), "192.168.1.1")]),
+                ]),
+            ),
+        ])
+    }
+
+    #[cfg(feature = "serde_json")]
+    fn json_record() -> serde_json::Value {
+        serde_json::json!({
+            "hostname": "Web-01",
+            "online": true,
+            "risk": 72,
+            "score": -1.5,
+            "empty": null,
+            "device": {
+                "os": { "name": "Windows", "build": 19045 },
+                "tags": ["prod", "dmz"],
+            },
+            "interfaces": [
+                { "ip": "10.0.0.1" },
+                { "ip": "192.168.1.1" },
+            ],
+        })
+    }
+
+    #[test]
+    fn operators() {
+        for record in records() {
+            for (input, expected) in [
+                ("risk:72", true),
+                ("risk:!72", false),
+                ("risk:>72", false),
+                ("risk:>=72", true),
+                ("risk:<100", true),
+                ("risk:<=71", false),
+                ("hostname:~'EB'", true),
+                ("hostname:!~'eb'", false),
+                ("online:true", true),
+                ("online:!true", false),
+                ("score:<0", true),
+                ("score:0", false),
+                ("hostname:>'A'", true),
+            ] {
+                assert_eq!(eval(input, record.as_ref()), Ok(expected), "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn exact_strings() {
+        for record in records() {
+            let record = record.as_ref();
+            assert_eq!(eval("hostname:'web-01'", record), Ok(true));
+            assert_eq!(eval("hostname:'web*'", record), Ok(true));
+            assert_eq!(eval("hostname:['web-01']", record), Ok(false));
+            assert_eq!(eval("hostname:['Web-01']", record), Ok(true));
+            assert_eq!(eval("hostname:['Web*']", record), Ok(false));
+            assert_eq!(eval("hostname:~['EB']", record), Ok(false));
+        }
+    }
+
+    #[test]
+    fn paths_and_arrays() {
+        for record in records() {
+            let record = record.as_ref();
+            assert_eq!(eval("device.os.name:'windows'", record), Ok(true));
+            assert_eq!(eval("device.os.build:>19000", record), Ok(true));
+            assert_eq!(eval("device.tags:'dmz'", record), Ok(true));
+            assert_eq!(eval("device.tags:!'dmz'", record), Ok(false));
+            assert_eq!(eval("device.tags:!'test'", record), Ok(true));
+            assert_eq!(eval("interfaces.ip:'192.*'", record), Ok(true));
+            assert_eq!(eval("interfaces.ip:!'10.*'", record), Ok(false));
+        }
+    }
+
+    #[test]
+    fn missing_values() {
+        for record in records() {
+            let record = record.as_ref();
+            assert_eq!(eval("nope:1", record), Ok(false));
+            assert_eq!(eval("nope:!1", record), Ok(true));
+            assert_eq!(eval("empty:'x'", record), Ok(false));
+            assert_eq!(eval("hostname.x:'x'", record), Ok(false));
+            assert_eq!(Filter::True.eval(record), Ok(true));
+            assert_eq!(Filter::Or(vec![]).eval(record), Ok(false));
+        }
+    }
+
+    #[test]
+    fn type_mismatches() {
+        for record in records() {
+            let record = record.as_ref();
+            assert_eq!(
+                eval("online:'yes'", record),
+                Err(EvalError::TypeMismatch {
+                    property: "online".parse().unwrap(),
+                    expected: ValueKind::String,
+                    found: ValueKind::Bool,
+                })
+            );
+            assert_eq!(
+                eval("device.os:'x'", record).unwrap_err().to_string(),
+                "Property 'device.os' is an object, but the filter expects a string"
+            );
+
+            // Evaluation stops before reaching the mismatched clause.
+            assert_eq!(eval("risk:1+online:'yes'", record), Ok(false));
+        }
+    }
+}
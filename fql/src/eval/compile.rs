@@ -0,0 +1,541 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use aho_corasick::AhoCorasick;
+use memchr::memmem::Finder;
+
+use crate::{ast::ClauseOperator, CostWeights, Filter, KnownValues, PropertyPath, Value};
+
+use super::{any_value, compare_float, EvalError, FieldValue, Record, ValueKind};
+
+impl Filter {
+    /// Prepare the filter to be evaluated against many records.
+    ///
+    /// Compiling does the work of [`Filter::eval`] which doesn't depend on the record
+    /// once: operands are converted and case-folded, and wildcard and substring
+    /// matchers are built. The children of each `And` and `Or` are reordered so the
+    /// cheapest are evaluated first, and constant parts of the filter are removed.
+    ///
+    /// Property paths are resolved to one entry per distinct path. When evaluating,
+    /// each path is looked up with [`Record::get`] at most once per record, the first
+    /// time a clause needs it, and clauses on the same property share the value.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// let compiled = fql::parse("hostname:~'prod'+platform:'Win*'")
+    ///     .to_filter()
+    ///     .unwrap()
+    ///     .compile();
+    ///
+    /// let record = HashMap::from([
+    ///     ("hostname".to_string(), "web-PROD-01"),
+    ///     ("platform".to_string(), "Windows"),
+    /// ]);
+    /// assert!(compiled.eval(&record).unwrap());
+    /// ```
+    pub fn compile(&self) -> CompiledFilter {
+        // Partial evaluation without any known values only removes constants.
+        let filter = self.partial_eval(&KnownValues::new());
+        let mut paths = Paths::default();
+        let root = Node::compile(&filter, &CostWeights::default(), &mut paths);
+        CompiledFilter {
+            root,
+            paths: paths.paths,
+        }
+    }
+}
+
+/// A filter prepared for fast, repeated evaluation by [`Filter::compile`].
+///
+/// A `CompiledFilter` is `Send` and `Sync`, so one can be shared by many threads.
+#[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    root: Node,
+    /// The distinct property paths the clauses read, indexed by [`Clause::slot`].
+    paths: Vec<PropertyPath>,
+}
+
+impl CompiledFilter {
+    /// Check whether `record` matches the filter.
+    ///
+    /// This gives the same result as [`Filter::eval`] on the original filter, except
+    /// that clauses are evaluated in a different order. When a record has values
+    /// which don't match the type of several operands, the error may be for a
+    /// different clause, and a mismatch may not be reported at all if a cheaper
+    /// clause decides the result first.
+    ///
+    /// # Errors
+    /// Evaluation fails if a value in the record can't be compared to the operand of
+    /// a clause, such as a string property compared to an integer.
+    pub fn eval<R: Record + ?Sized>(&self, record: &R) -> Result<bool, EvalError> {
+        let mut values = Values {
+            record,
+            paths: &self.paths,
+            values: vec![None; self.paths.len()],
+        };
+
+        self.root.eval(&mut values)
+    }
+}
+
+/// Interns the distinct property paths of the filter being compiled.
+#[derive(Default)]
+struct Paths {
+    paths: Vec<PropertyPath>,
+    indices: BTreeMap<PropertyPath, usize>,
+}
+
+impl Paths {
+    fn intern(&mut self, path: &PropertyPath) -> usize {
+        if let Some(index) = self.indices.get(path) {
+            return *index;
+        }
+
+        let index = self.paths.len();
+        self.paths.push(path.clone());
+        self.indices.insert(path.clone(), index);
+        index
+    }
+}
+
+/// The values of one record's properties, each looked up the first time a clause
+/// needs it.
+struct Values<'r, R: ?Sized> {
+    record: &'r R,
+    paths: &'r [PropertyPath],
+    values: Vec<Option<FieldValue<'r>>>,
+}
+
+impl<'r, R: Record + ?Sized> Values<'r, R> {
+    fn get(&mut self, slot: usize) -> &FieldValue<'r> {
+        let (record, paths) = (self.record, self.paths);
+        self.values[slot].get_or_insert_with(|| record.get(&paths[slot]))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Clause(Box<Clause>),
+    Const(bool),
+}
+
+impl Node {
+    fn compile(filter: &Filter, weights: &CostWeights, paths: &mut Paths) -> Self {
+        match filter {
+            Filter::And(children) => Self::And(Self::compile_children(children, weights, paths)),
+            Filter::Or(children) => Self::Or(Self::compile_children(children, weights, paths)),
+            Filter::Clause(predicate) => Self::Clause(Box::new(Clause {
+                property: predicate.property.clone(),
+                slot: paths.intern(&predicate.property),
+                expected: ValueKind::of(&predicate.value),
+                negated: predicate.operator.is_negated(),
+                matcher: Matcher::new(predicate.operator, &predicate.value, predicate.exact),
+            })),
+            Filter::True => Self::Const(true),
+            Filter::False => Self::Const(false),
+        }
+    }
+
+    /// Compile `children`, ordered from the cheapest to the most expensive.
+    fn compile_children(
+        children: &[Filter],
+        weights: &CostWeights,
+        paths: &mut Paths,
+    ) -> Vec<Self> {
+        let mut children = children
+            .iter()
+            .map(|child| {
+                let node = Self::compile(child, weights, paths);
+                (node.cost(weights), node)
+            })
+            .collect::<Vec<_>>();
+
+        children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        children.into_iter().map(|(_, node)| node).collect()
+    }
+
+    /// The estimated cost of evaluating the node, using the same weights as
+    /// [`Metrics::cost`](crate::Metrics::cost).
+    fn cost(&self, weights: &CostWeights) -> f64 {
+        match self {
+            Self::And(children) | Self::Or(children) => {
+                weights.depth + children.iter().map(|c| c.cost(weights)).sum::<f64>()
+            }
+            Self::Clause(clause) => match clause.matcher {
+                Matcher::Wildcard(_) => weights.clause + weights.wildcard,
+                Matcher::Contains(_) | Matcher::ContainsFolded { .. } => {
+                    weights.clause + weights.contains
+                }
+                _ => weights.clause,
+            },
+            Self::Const(_) => 0.0,
+        }
+    }
+
+    fn eval<R: Record + ?Sized>(&self, values: &mut Values<'_, R>) -> Result<bool, EvalError> {
+        match self {
+            Self::And(children) => {
+                for child in children {
+                    if !child.eval(values)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            Self::Or(children) => {
+                for child in children {
+                    if child.eval(values)? {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
+            }
+            Self::Clause(clause) => clause.eval(values.get(clause.slot)),
+            Self::Const(value) => Ok(*value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    property: PropertyPath,
+    /// The index of the property in [`CompiledFilter::paths`].
+    slot: usize,
+    expected: ValueKind,
+    negated: bool,
+    /// Matches values which satisfy the positive form of the clause.
+    matcher: Matcher,
+}
+
+impl Clause {
+    fn eval(&self, value: &FieldValue<'_>) -> Result<bool, EvalError> {
+        let found = any_value(value, &mut |value| self.eval_value(value))?;
+        Ok(found != self.negated)
+    }
+
+    fn eval_value(&self, value: &FieldValue<'_>) -> Result<bool, EvalError> {
+        Ok(match (&self.matcher, value) {
+            (Matcher::Bool(expected), FieldValue::Bool(actual)) => actual == expected,
+            (Matcher::Int(operator, expected), FieldValue::Int(actual)) => {
+                compare(*operator, actual, expected)
+            }
+            (Matcher::Int(operator, expected), FieldValue::Number(actual)) => {
+                compare_float(*operator, *actual, *expected)
+            }
+            (Matcher::Never, _) if self.expected == ValueKind::of_field(value) => false,
+            (Matcher::Never, FieldValue::Number(_)) if self.expected == ValueKind::Integer => false,
+            (matcher, FieldValue::Str(actual)) if self.expected == ValueKind::String => {
+                matcher.matches_str(actual)
+            }
+            _ => {
+                return Err(EvalError::TypeMismatch {
+                    property: self.property.clone(),
+                    expected: self.expected,
+                    found: ValueKind::of_field(value),
+                })
+            }
+        })
+    }
+}
+
+/// A test for a single value, equivalent to [`semantics::matches`](crate::semantics)
+/// for the positive form of the operator.
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// Matches no value of the expected type, such as `~` with an integer.
+    Never,
+    Bool(bool),
+    Int(ClauseOperator, u64),
+    /// Compares strings by their UTF-8 bytes, with `>`, `<`, and so on.
+    StrCompare(ClauseOperator, Box<str>),
+    StrEq(Box<str>),
+    /// Matches strings which are equal to the operand, ignoring case.
+    StrEqFolded(Box<str>),
+    Wildcard(Wildcard),
+    Contains(Finder<'static>),
+    /// Matches strings which contain the operand, ignoring case.
+    ContainsFolded {
+        finder: Finder<'static>,
+        /// A matcher for ASCII text, which avoids folding the value. Only set if the
+        /// operand is ASCII.
+        ascii: Option<AhoCorasick>,
+    },
+}
+
+impl Matcher {
+    fn new(operator: ClauseOperator, value: &Value, exact: bool) -> Self {
+        let positive = match operator {
+            ClauseOperator::Ne => ClauseOperator::Eq,
+            ClauseOperator::NotContains => ClauseOperator::Contains,
+            other => other,
+        };
+
+        match (positive, value) {
+            (ClauseOperator::Eq, Value::Bool(expected)) => Self::Bool(*expected),
+            (_, Value::Bool(_)) | (ClauseOperator::Contains, Value::Int(_)) => Self::Never,
+            (_, Value::Int(expected)) => Self::Int(positive, *expected),
+            (ClauseOperator::Eq, Value::Str(expected)) if exact => {
+                Self::StrEq(expected.as_str().into())
+            }
+            (ClauseOperator::Eq, Value::Str(expected)) if expected.contains('*') => {
+                Self::Wildcard(Wildcard::new(&expected.to_lowercase()))
+            }
+            (ClauseOperator::Eq, Value::Str(expected)) => {
+                Self::StrEqFolded(expected.to_lowercase().into())
+            }
+            (ClauseOperator::Contains, Value::Str(expected)) if exact => {
+                Self::Contains(Finder::new(expected.as_bytes()).into_owned())
+            }
+            (ClauseOperator::Contains, Value::Str(expected)) => {
+                let folded = expected.to_lowercase();
+                Self::ContainsFolded {
+                    finder: Finder::new(folded.as_bytes()).into_owned(),
+                    ascii: expected.is_ascii().then(|| {
+                        AhoCorasick::builder()
+                            .ascii_case_insensitive(true)
+                            .build([expected])
+                            .expect("a single pattern is within the size limits")
+                    }),
+                }
+            }
+            (_, Value::Str(expected)) => Self::StrCompare(positive, expected.as_str().into()),
+        }
+    }
+
+    fn matches_str(&self, actual: &str) -> bool {
+        match self {
+            Self::StrCompare(operator, expected) => compare(*operator, actual, &**expected),
+            Self::StrEq(expected) => actual == &**expected,
+            Self::StrEqFolded(expected) => fold(actual) == **expected,
+            Self::Wildcard(wildcard) => wildcard.matches(&fold(actual)),
+            Self::Contains(finder) => finder.find(actual.as_bytes()).is_some(),
+            Self::ContainsFolded { finder, ascii } => match ascii {
+                // Folding non-ASCII text can produce ASCII characters, such as the
+                // Kelvin sign becoming `k`, so only ASCII text can skip it.
+                Some(ascii) if actual.is_ascii() => ascii.is_match(actual),
+                _ => finder.find(fold(actual).as_bytes()).is_some(),
+            },
+            Self::Never | Self::Bool(_) | Self::Int(..) => false,
+        }
+    }
+}
+
+/// Lowercase `text`, borrowing it if it's already lowercase ASCII.
+fn fold(text: &str) -> Cow<'_, str> {
+    if text
+        .bytes()
+        .all(|b| b.is_ascii() && !b.is_ascii_uppercase())
+    {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.to_lowercase())
+    }
+}
+
+fn compare<T: Ord + ?Sized>(operator: ClauseOperator, actual: &T, expected: &T) -> bool {
+    match operator {
+        ClauseOperator::Eq => actual == expected,
+        ClauseOperator::Gt => actual > expected,
+        ClauseOperator::Ge => actual >= expected,
+        ClauseOperator::Lt => actual < expected,
+        ClauseOperator::Le => actual <= expected,
+        _ => false,
+    }
+}
+
+/// A pattern where `*` matches any sequence of characters.
+///
+/// The pattern is split at each `*`. The text must start with the first part and
+/// end with the last, and contain the others in order between them. Finding each
+/// middle part at its leftmost position is enough, since the `*` after it can absorb
+/// anything a later position would skip.
+#[derive(Debug, Clone)]
+struct Wildcard {
+    prefix: Box<str>,
+    middle: Vec<Finder<'static>>,
+    suffix: Box<str>,
+}
+
+impl Wildcard {
+    fn new(pattern: &str) -> Self {
+        let mut parts = pattern.split('*');
+        let prefix = parts.next().unwrap_or_default().into();
+        let mut middle = parts
+            .filter(|part| !part.is_empty())
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>();
+
+        // There is at least one `*`, so the last part is the suffix unless it is
+        // empty, in which case the pattern ends with `*`.
+        let suffix = if pattern.ends_with('*') {
+            Box::default()
+        } else {
+            middle.pop().unwrap_or_default().into()
+        };
+
+        Self {
+            prefix,
+            middle: middle
+                .iter()
+                .map(|part| Finder::new(part.as_bytes()).into_owned())
+                .collect(),
+            suffix,
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        if text.len() < self.prefix.len() + self.suffix.len()
+            || !text.starts_with(&*self.prefix)
+            || !text.ends_with(&*self.suffix)
+        {
+            return false;
+        }
+
+        let mut rest = &text.as_bytes()[self.prefix.len()..text.len() - self.suffix.len()];
+        for finder in &self.middle {
+            match finder.find(rest) {
+                Some(start) => rest = &rest[start + finder.needle().len()..],
+                None => return false,
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::HashMap};
+
+    use crate::{
+        eval::{FieldValue, Record},
+        filter::filter,
+        PropertyPath,
+    };
+
+    use super::{CompiledFilter, Node, Wildcard};
+
+    fn record() -> HashMap<String, Vec<&'static str>> {
+        HashMap::from([
+            ("hostname".to_string(), vec!["Web-PROD-01"]),
+            ("tags".to_string(), vec!["dmz", "prod"]),
+            ("os".to_string(), vec!["Straße"]),
+        ])
+    }
+
+    /// Check the compiled filter agrees with the uncompiled one.
+    #[track_caller]
+    fn eval(input: &str) -> bool {
+        let filter = filter(input);
+        let record = record();
+        let expected = filter.eval(&record).unwrap();
+        assert_eq!(filter.compile().eval(&record).unwrap(), expected, "{input}");
+        expected
+    }
+
+    #[test]
+    fn wildcards() {
+        for (pattern, text, expected) in [
+            ("a*", "abc", true),
+            ("*c", "abc", true),
+            ("a*c", "ac", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "acb", false),
+            ("ab*ba", "aba", false),
+            ("*", "", true),
+            ("**x**", "axb", true),
+            ("*b*b*", "ab", false),
+        ] {
+            assert_eq!(
+                Wildcard::new(pattern).matches(text),
+                expected,
+                "{pattern} {text}"
+            );
+        }
+    }
+
+    #[test]
+    fn agrees_with_eval() {
+        assert!(eval("hostname:'web-prod-01'"));
+        assert!(!eval("hostname:['web-prod-01']"));
+        assert!(eval("hostname:'WEB*01'"));
+        assert!(!eval("hostname:['WEB*01']"));
+        assert!(eval("hostname:~'prod'"));
+        assert!(!eval("hostname:~['prod']"));
+        assert!(eval("hostname:!~'test'"));
+        assert!(eval("hostname:>'A'"));
+        assert!(eval("os:~'STRASSE',os:~'straSSe',os:'straße'"));
+        assert!(eval("tags:'dmz'+tags:!'test'"));
+        assert!(!eval("tags:!'prod'"));
+        assert!(eval("missing:!'x'"));
+        assert!(!eval("missing:'*'"));
+    }
+
+    #[test]
+    fn cheapest_first() {
+        let compiled = filter("a:~'x'+b:'y*'+(c:1,d:2)+e:3").compile();
+        let Node::And(children) = &compiled.root else {
+            panic!("{compiled:?}");
+        };
+
+        let order = children
+            .iter()
+            .map(|child| match child {
+                Node::Clause(clause) => clause.property.to_string(),
+                _ => "group".to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["e", "group", "b", "a"]);
+    }
+
+    #[test]
+    fn short_circuits() {
+        let record = HashMap::from([("risk".to_string(), 5)]);
+
+        // The cheaper clause is evaluated first, so the mismatched one never is.
+        let filter = filter("risk:~'x'+risk:1");
+        assert!(filter.eval(&record).is_err());
+        assert_eq!(filter.compile().eval(&record), Ok(false));
+
+        let filter = self::filter("risk:~'x',risk:5");
+        assert!(filter.eval(&record).is_err());
+        assert_eq!(filter.compile().eval(&record), Ok(true));
+    }
+
+    /// Clauses on the same property share one lookup per record.
+    #[test]
+    fn looks_up_each_path_once() {
+        struct Counting {
+            record: HashMap<String, u64>,
+            lookups: Cell<usize>,
+        }
+
+        impl Record for Counting {
+            fn get(&self, path: &PropertyPath) -> FieldValue<'_> {
+                self.lookups.set(self.lookups.get() + 1);
+                Record::get(&self.record, path)
+            }
+        }
+
+        let compiled = filter("risk:>1+risk:<9+(risk:!5,host.id:1)").compile();
+        assert_eq!(compiled.paths.len(), 2);
+
+        let record = Counting {
+            record: HashMap::from([("risk".to_string(), 3)]),
+            lookups: Cell::new(0),
+        };
+        assert_eq!(compiled.eval(&record), Ok(true));
+        assert_eq!(record.lookups.get(), 1);
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledFilter>();
+    }
+}
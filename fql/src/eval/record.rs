@@ -0,0 +1,266 @@
+use std::{borrow::Cow, collections::HashMap, hash::BuildHasher};
+
+use crate::PropertyPath;
+
+/// A record which filters can be evaluated against with [`Filter::eval`](crate::Filter::eval).
+///
+/// Most types should implement [`Field`] and forward to it, which lets them also be
+/// nested inside other records. With the `derive` feature, `#[derive(Record)]`
+/// implements both traits for a struct with named fields; see its documentation for
+/// the attributes it supports.
+pub trait Record {
+    /// The value of the property at `path`, or [`FieldValue::Missing`] if there is none.
+    fn get(&self, path: &PropertyPath) -> FieldValue<'_>;
+}
+
+/// A value in a record, which may have fields of its own.
+pub trait Field {
+    /// The value at `path` below this one, which is this value itself if `path` is empty.
+    ///
+    /// Values without fields should return [`FieldValue::Missing`] for any other path.
+    fn field(&self, path: &[String]) -> FieldValue<'_>;
+}
+
+/// A value read from a record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue<'a> {
+    /// The record has no value for the property. `None` and JSON `null` are missing.
+    Missing,
+    Str(Cow<'a, str>),
+    Int(u64),
+    /// A number which isn't a non-negative integer.
+    Number(f64),
+    Bool(bool),
+    /// Several values, any of which may match a clause.
+    Array(Vec<FieldValue<'a>>),
+    /// A value with fields of its own, which can't be compared to an operand.
+    Object,
+}
+
+impl<'a> From<&'a str> for FieldValue<'a> {
+    fn from(v: &'a str) -> Self {
+        Self::Str(Cow::Borrowed(v))
+    }
+}
+
+impl From<u64> for FieldValue<'_> {
+    fn from(v: u64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<i64> for FieldValue<'_> {
+    fn from(v: i64) -> Self {
+        match u64::try_from(v) {
+            Ok(v) => Self::Int(v),
+            Err(_) => Self::Number(v as f64),
+        }
+    }
+}
+
+impl From<f64> for FieldValue<'_> {
+    fn from(v: f64) -> Self {
+        Self::Number(v)
+    }
+}
+
+impl From<bool> for FieldValue<'_> {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+/// Implement [`Field`] for types which have no fields, converting `$v` with `$convert`.
+macro_rules! scalar_field {
+    ($($ty:ty, $v:ident => $convert:expr;)*) => {
+        $(
+            impl Field for $ty {
+                fn field(&self, path: &[String]) -> FieldValue<'_> {
+                    let $v = self;
+                    if path.is_empty() {
+                        $convert
+                    } else {
+                        FieldValue::Missing
+                    }
+                }
+            }
+        )*
+    };
+}
+
+scalar_field! {
+    str, v => FieldValue::from(v);
+    String, v => FieldValue::from(v.as_str());
+    bool, v => FieldValue::Bool(*v);
+    u8, v => FieldValue::Int(u64::from(*v));
+    u16, v => FieldValue::Int(u64::from(*v));
+    u32, v => FieldValue::Int(u64::from(*v));
+    u64, v => FieldValue::Int(*v);
+    usize, v => FieldValue::Int(*v as u64);
+    i8, v => FieldValue::from(i64::from(*v));
+    i16, v => FieldValue::from(i64::from(*v));
+    i32, v => FieldValue::from(i64::from(*v));
+    i64, v => FieldValue::from(*v);
+    isize, v => FieldValue::from(*v as i64);
+    f32, v => FieldValue::Number(f64::from(*v));
+    f64, v => FieldValue::Number(*v);
+}
+
+impl<T: Field + ?Sized> Field for &T {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        (**self).field(path)
+    }
+}
+
+impl<T: Field + ?Sized> Field for Box<T> {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        (**self).field(path)
+    }
+}
+
+impl<T: Field> Field for Option<T> {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        match self {
+            Some(value) => value.field(path),
+            None => FieldValue::Missing,
+        }
+    }
+}
+
+/// The path is resolved in each element, so `interfaces.ip` finds the `ip` of every
+/// element of `interfaces`.
+impl<T: Field> Field for [T] {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        FieldValue::Array(self.iter().map(|element| element.field(path)).collect())
+    }
+}
+
+impl<T: Field> Field for Vec<T> {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        self.as_slice().field(path)
+    }
+}
+
+impl<V: Field, S: BuildHasher> Field for HashMap<String, V, S> {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        match path.split_first() {
+            Some((first, rest)) => self
+                .get(first)
+                .map_or(FieldValue::Missing, |value| value.field(rest)),
+            None => FieldValue::Object,
+        }
+    }
+}
+
+impl<V: Field, S: BuildHasher> Record for HashMap<String, V, S> {
+    fn get(&self, path: &PropertyPath) -> FieldValue<'_> {
+        self.field(path.segments())
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Field for serde_json::Value {
+    fn field(&self, path: &[String]) -> FieldValue<'_> {
+        use serde_json::Value as Json;
+
+        match (self, path.split_first()) {
+            (Json::Null, _) => FieldValue::Missing,
+            (Json::Array(elements), _) => elements.field(path),
+            (Json::Object(fields), Some((first, rest))) => fields
+                .get(first)
+                .map_or(FieldValue::Missing, |value| value.field(rest)),
+            (Json::Object(_), None) => FieldValue::Object,
+            (Json::String(s), None) => FieldValue::from(s.as_str()),
+            (Json::Bool(b), None) => FieldValue::Bool(*b),
+            (Json::Number(n), None) => match n.as_u64() {
+                Some(n) => FieldValue::Int(n),
+                None => n.as_f64().map_or(FieldValue::Object, FieldValue::Number),
+            },
+            (_, Some(_)) => FieldValue::Missing,
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Record for serde_json::Value {
+    fn get(&self, path: &PropertyPath) -> FieldValue<'_> {
+        self.field(path.segments())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{parse, PropertyPath};
+
+    use super::{Field, FieldValue, Record};
+
+    #[track_caller]
+    fn path(input: &str) -> PropertyPath {
+        input.parse().unwrap()
+    }
+
+    /// A record with a nested field, implemented by hand as the derive macro would.
+    struct Host {
+        name: String,
+        risk: Option<i32>,
+        interfaces: Vec<HashMap<String, String>>,
+    }
+
+    impl Field for Host {
+        fn field(&self, path: &[String]) -> FieldValue<'_> {
+            match path.split_first() {
+                Some((first, rest)) => match first.as_str() {
+                    "name" => self.name.field(rest),
+                    "risk" => self.risk.field(rest),
+                    "interfaces" => self.interfaces.field(rest),
+                    _ => FieldValue::Missing,
+                },
+                None => FieldValue::Object,
+            }
+        }
+    }
+
+    impl Record for Host {
+        fn get(&self, path: &PropertyPath) -> FieldValue<'_> {
+            self.field(path.segments())
+        }
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!("x".field(&[]), FieldValue::from("x"));
+        assert_eq!((-3i8).field(&[]), FieldValue::Number(-3.0));
+        assert_eq!(3u16.field(&[]), FieldValue::Int(3));
+        assert_eq!(true.field(&["x".into()]), FieldValue::Missing);
+        assert_eq!(None::<u8>.field(&[]), FieldValue::Missing);
+    }
+
+    #[test]
+    fn hash_map() {
+        let record = HashMap::from([("a".to_string(), HashMap::from([("b".to_string(), 1)]))]);
+        assert_eq!(Record::get(&record, &path("a.b")), FieldValue::Int(1));
+        assert_eq!(Record::get(&record, &path("a")), FieldValue::Object);
+        assert_eq!(Record::get(&record, &path("a.c")), FieldValue::Missing);
+        assert_eq!(Record::get(&record, &path("a.b.c")), FieldValue::Missing);
+    }
+
+    #[test]
+    fn custom_record() {
+        let host = Host {
+            name: "web-01".into(),
+            risk: None,
+            interfaces: vec![
+                HashMap::from([("ip".to_string(), "10.0.0.1".to_string())]),
+                HashMap::from([("ip".to_string(), "192.168.1.1".to_string())]),
+            ],
+        };
+
+        let eval = |input: &str| parse(input).to_filter().unwrap().eval(&host).unwrap();
+        assert!(eval("name:'web*'+interfaces.ip:'192.*'"));
+        assert!(eval("risk:!5"));
+        assert!(!eval("risk:<5"));
+        assert!(!eval("interfaces.ip:!'10.*'"));
+    }
+}
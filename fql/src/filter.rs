@@ -0,0 +1,436 @@
+use std::fmt;
+
+use crate::{
+    ast::{self, ClauseOperator, Expr, Lit},
+    syntax::SyntaxKind,
+    Parse, PropertyPath,
+};
+
+mod fingerprint;
+mod partial;
+mod split;
+
+pub use self::partial::KnownValues;
+
+/// A lowered representation of a filter, with no syntax details.
+///
+/// Unlike the AST, a `Filter` has no spans or parentheses, and it is only produced
+/// from well-formed input. Chains of the same boolean operator are flattened into
+/// a single node, so `a:1+b:2+c:3` lowers to one [`Filter::And`] with three children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Filter {
+    /// Matches when every child matches. An empty `And` matches everything.
+    And(Vec<Filter>),
+    /// Matches when at least one child matches. An empty `Or` matches nothing.
+    Or(Vec<Filter>),
+    Clause(Predicate),
+    /// Matches every record, like an empty `And`.
+    ///
+    /// This is not produced by lowering, but by simplifications such as
+    /// [`Filter::partial_eval`] which can decide a filter without seeing a record.
+    True,
+    /// Matches no record, like an empty `Or`.
+    False,
+}
+
+impl Filter {
+    /// Lower an expression into a filter.
+    ///
+    /// Returns `None` if any part of the expression is missing or malformed, such as
+    /// a clause without an operand or an integer literal which does not fit in a `u64`.
+    ///
+    /// # Example
+    /// ```rust
+    /// let expr = fql::parse("a:1+(b:2+c:3)").to_expr().unwrap();
+    /// let filter = fql::Filter::lower(&expr).unwrap();
+    /// assert_eq!(filter.to_string(), "a:1+b:2+c:3");
+    /// ```
+    pub fn lower(expr: &Expr) -> Option<Self> {
+        enum Step {
+            Lower(Expr),
+            /// Combine the two most recently lowered filters with the operator.
+            Combine(SyntaxKind),
+        }
+
+        // Walk the tree with an explicit stack rather than recursion, so deeply nested
+        // expressions can't overflow the call stack.
+        let mut steps = vec![Step::Lower(expr.clone())];
+        let mut lowered = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Lower(Expr::Binary(binary)) => {
+                    steps.push(Step::Combine(binary.op()?.kind()));
+                    steps.push(Step::Lower(binary.rhs()?));
+                    steps.push(Step::Lower(binary.lhs()?));
+                }
+                Step::Lower(Expr::Paren(paren)) => steps.push(Step::Lower(paren.body()?)),
+                Step::Lower(Expr::Clause(clause)) => {
+                    lowered.push(Self::Clause(Predicate::lower(&clause)?));
+                }
+                Step::Combine(kind) => {
+                    let rhs = lowered.pop()?;
+                    let lhs = lowered.pop()?;
+                    lowered.push(match kind {
+                        SyntaxKind::Plus => Self::And(vec![lhs, rhs]).flatten(),
+                        SyntaxKind::Comma => Self::Or(vec![lhs, rhs]).flatten(),
+                        _ => return None,
+                    });
+                }
+            }
+        }
+
+        lowered.pop()
+    }
+
+    /// Rewrite the filter into a canonical form.
+    ///
+    /// Nested nodes of the same kind are flattened, children are sorted and
+    /// deduplicated, and single-child `And` and `Or` nodes are replaced by their child.
+    /// `True` and `False` are dropped from nodes they don't affect, and an `And` with a
+    /// `False` child or an `Or` with a `True` child is replaced by that constant. An
+    /// empty `And` is replaced by `True`, and an empty `Or` by `False`.
+    /// Two filters which differ only in these respects normalize to equal values.
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::And(children) => Self::normalize_children(children, true),
+            Self::Or(children) => Self::normalize_children(children, false),
+            Self::Clause(_) | Self::True | Self::False => self,
+        }
+    }
+
+    fn normalize_children(children: Vec<Filter>, is_and: bool) -> Self {
+        let mut flat = Vec::with_capacity(children.len());
+        for child in children {
+            match (child.normalize(), is_and) {
+                (Self::And(nested), true) | (Self::Or(nested), false) => flat.extend(nested),
+                // These are the identities of `And` and `Or`, like an empty child.
+                (Self::True, true) | (Self::False, false) => {}
+                // These decide the result of `And` and `Or` whatever the other children are.
+                (Self::False, true) => return Self::False,
+                (Self::True, false) => return Self::True,
+                (other, _) => flat.push(other),
+            }
+        }
+
+        flat.sort();
+        flat.dedup();
+
+        if flat.len() == 1 {
+            flat.pop().unwrap()
+        } else if flat.is_empty() {
+            // Every child was an identity, or there were none.
+            if is_and {
+                Self::True
+            } else {
+                Self::False
+            }
+        } else if is_and {
+            Self::And(flat)
+        } else {
+            Self::Or(flat)
+        }
+    }
+
+    /// Merge directly-nested children of the same kind into this node.
+    fn flatten(self) -> Self {
+        match self {
+            Self::And(children) => Self::And(
+                children
+                    .into_iter()
+                    .flat_map(|child| match child {
+                        Self::And(nested) => nested,
+                        other => vec![other],
+                    })
+                    .collect(),
+            ),
+            Self::Or(children) => Self::Or(
+                children
+                    .into_iter()
+                    .flat_map(|child| match child {
+                        Self::Or(nested) => nested,
+                        other => vec![other],
+                    })
+                    .collect(),
+            ),
+            Self::Clause(_) | Self::True | Self::False => self,
+        }
+    }
+
+    /// Iterate through the predicates in the filter, in the order they appear.
+    pub fn predicates(&self) -> impl Iterator<Item = &Predicate> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || loop {
+            match stack.pop()? {
+                Self::And(children) | Self::Or(children) => stack.extend(children.iter().rev()),
+                Self::Clause(predicate) => return Some(predicate),
+                Self::True | Self::False => {}
+            }
+        })
+    }
+}
+
+/// Display the filter as FQL text.
+///
+/// Parentheses are only added where precedence requires them. `True` and `False`
+/// have no FQL syntax, so inside `And` and `Or` they are simplified away first, as
+/// are empty groups. Only a filter which is constant as a whole is written without
+/// clauses: `True` as an empty string, matching the API convention that an empty
+/// filter matches everything, and `False` as `()`, which has no FQL equivalent.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.has_nested_constants() {
+            self.partial_eval(&KnownValues::new()).write(f)
+        } else {
+            self.write(f)
+        }
+    }
+}
+
+impl Filter {
+    /// Whether `True`, `False`, or an empty group appears inside an `And` or `Or`.
+    fn has_nested_constants(&self) -> bool {
+        let mut stack = vec![self];
+        while let Some(filter) = stack.pop() {
+            if let Self::And(children) | Self::Or(children) = filter {
+                for child in children {
+                    match child {
+                        Self::True | Self::False => return true,
+                        Self::And(nested) | Self::Or(nested) if nested.is_empty() => return true,
+                        _ => stack.push(child),
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Write the filter, which has no constants inside `And` or `Or`.
+    fn write(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::And(children) => {
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("+")?;
+                    }
+
+                    if matches!(child, Self::Or(c) if c.len() != 1) {
+                        f.write_str("(")?;
+                        child.write(f)?;
+                        f.write_str(")")?;
+                    } else {
+                        child.write(f)?;
+                    }
+                }
+
+                Ok(())
+            }
+            Self::Or(children) if children.is_empty() => f.write_str("()"),
+            Self::Or(children) => {
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+
+                    child.write(f)?;
+                }
+
+                Ok(())
+            }
+            Self::Clause(predicate) => write!(f, "{predicate}"),
+            Self::True => Ok(()),
+            Self::False => f.write_str("()"),
+        }
+    }
+}
+
+impl From<Predicate> for Filter {
+    fn from(v: Predicate) -> Self {
+        Self::Clause(v)
+    }
+}
+
+/// A single lowered clause, such as `platform_name:'Windows'`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Predicate {
+    pub property: PropertyPath,
+    pub operator: ClauseOperator,
+    pub value: Value,
+    /// Whether the operand was wrapped in brackets, requesting an exact match.
+    pub exact: bool,
+}
+
+impl Predicate {
+    pub(crate) fn lower(clause: &ast::Clause) -> Option<Self> {
+        let property = PropertyPath::from(clause.property()?);
+        let operand = clause.operand()?;
+        let value = match operand.literal()?.value()? {
+            Lit::Str(s) => Value::Str(s.value().into_owned()),
+            Lit::Int(i) => Value::Int(i.value().ok()?),
+            Lit::Bool(b) => Value::Bool(b.value()),
+        };
+
+        Some(Self {
+            property,
+            operator: clause.operator_kind(),
+            value,
+            exact: operand.is_exact(),
+        })
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.property, self.operator)?;
+        if self.exact {
+            write!(f, "[{}]", self.value)
+        } else {
+            write!(f, "{}", self.value)
+        }
+    }
+}
+
+/// The value of a lowered literal.
+///
+/// The `Display` implementation writes the value as FQL literal text. FQL strings
+/// have no escapes, so a string containing a single quote doesn't display as a
+/// valid literal; use [`quote_str`](crate::quote_str) to check.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Self::Str(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Self::Str(v)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "'{s}'"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Bool(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+impl Parse {
+    /// Lower the parse result into a [`Filter`].
+    ///
+    /// Returns `None` if the input had any diagnostics, or if it could not be lowered.
+    pub fn to_filter(&self) -> Option<Filter> {
+        if self.diagnostics().next().is_some() {
+            return None;
+        }
+
+        Filter::lower(&self.to_expr()?)
+    }
+}
+
+/// Lower `input`, which must parse without errors. This is shared by the tests of
+/// every module which works with lowered filters.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn filter(input: &str) -> Filter {
+    crate::parse(input).to_filter().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::{filter, Filter};
+
+    #[test]
+    fn lower_flattens_chains() {
+        assert_eq!(
+            filter("a:1+b:2+(c:3+d:4)"),
+            Filter::And(vec![
+                filter("a:1"),
+                filter("b:2"),
+                filter("c:3"),
+                filter("d:4")
+            ])
+        );
+    }
+
+    #[test]
+    fn lower_incomplete() {
+        assert_eq!(parse("a:1+").to_filter(), None);
+        assert_eq!(parse("a:").to_filter(), None);
+    }
+
+    #[test]
+    fn normalize_ignores_order_and_duplicates() {
+        assert_eq!(
+            filter("b:2+(a:1,c:'x')+b:2").normalize(),
+            filter("(c:'x',a:1)+b:2").normalize()
+        );
+    }
+
+    #[test]
+    fn normalize_constants() {
+        let x = filter("a:1");
+        assert_eq!(
+            Filter::And(vec![Filter::True, x.clone()]).normalize(),
+            x.clone()
+        );
+        assert_eq!(
+            Filter::Or(vec![Filter::True, x.clone()]).normalize(),
+            Filter::True
+        );
+        assert_eq!(
+            Filter::And(vec![x.clone(), Filter::Or(vec![Filter::False])]).normalize(),
+            Filter::False
+        );
+        assert_eq!(Filter::Or(vec![Filter::False, x.clone()]).normalize(), x);
+    }
+
+    #[test]
+    fn display_adds_required_parens() {
+        assert_eq!(filter("a:1+(b:!2,c:~'x')").to_string(), "a:1+(b:!2,c:~'x')");
+        assert_eq!(filter("(a:1+b:2),c:['x']").to_string(), "a:1+b:2,c:['x']");
+    }
+
+    #[test]
+    fn display_simplifies_nested_constants() {
+        let a = || filter("a:1");
+        let display = |filter: Filter| filter.to_string();
+        assert_eq!(display(Filter::And(vec![Filter::True, a()])), "a:1");
+        assert_eq!(display(Filter::Or(vec![Filter::False, a()])), "a:1");
+        assert_eq!(display(Filter::And(vec![Filter::False, a()])), "()");
+        assert_eq!(display(Filter::Or(vec![Filter::True, a()])), "");
+        assert_eq!(display(Filter::Or(vec![Filter::And(vec![]), a()])), "");
+        assert_eq!(
+            display(Filter::And(vec![
+                a(),
+                Filter::Or(vec![Filter::Or(vec![]), filter("b:2,c:3")])
+            ])),
+            "a:1+(b:2,c:3)"
+        );
+        assert_eq!(display(Filter::True), "");
+        assert_eq!(display(Filter::Or(vec![])), "()");
+    }
+}
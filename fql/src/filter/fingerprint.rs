@@ -0,0 +1,209 @@
+use sha2::{Digest, Sha256};
+
+use crate::ast::ClauseOperator;
+
+use super::{Filter, Predicate, Value};
+
+/// Prefix hashed ahead of the encoding, so a future change to the encoding cannot
+/// collide with fingerprints computed by this version.
+const DOMAIN: &[u8] = b"fql-fingerprint-v1\0";
+
+impl Filter {
+    /// Compute a SHA-256 fingerprint of the filter's canonical form.
+    ///
+    /// Filters which differ only in parentheses, the order of children in an `And`
+    /// or `Or`, or repeated clauses have the same fingerprint.
+    ///
+    /// # Stability
+    /// The fingerprint is computed from a fixed, versioned encoding which does its own
+    /// canonicalization, rather than from [`Filter::normalize`] or the `Hash` or `Ord`
+    /// implementations, so it is stable across versions of this crate and across
+    /// platforms. If the encoding ever has to change, it will get a
+    /// new version prefix and the change will be released as a breaking change.
+    ///
+    /// # Example
+    /// ```rust
+    /// let a = fql::parse("a:1+(b:2,c:3)").to_filter().unwrap();
+    /// let b = fql::parse("(c:3,b:2,b:2)+a:1").to_filter().unwrap();
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(DOMAIN);
+        hasher.update(Canonical::new(self).encode());
+        hasher.finalize().into()
+    }
+}
+
+/// A filter in the canonical form which is fingerprinted.
+///
+/// Nested groups of the same kind are flattened, `True` and `False` are treated as an
+/// empty `And` and `Or`, so they vanish from groups of the same kind, and groups with a
+/// single member are replaced by that member. Members are sorted and deduplicated by
+/// their encoded bytes, so the order is defined by the encoding itself rather than by
+/// `Ord`.
+///
+/// This is deliberately separate from [`Filter::normalize`], so changes to
+/// normalization can't change fingerprints.
+enum Canonical {
+    Clause(Vec<u8>),
+    Group {
+        is_and: bool,
+        members: Vec<Canonical>,
+    },
+}
+
+impl Canonical {
+    fn new(filter: &Filter) -> Self {
+        let (is_and, children) = match filter {
+            Filter::Clause(predicate) => return Self::Clause(encode_predicate(predicate)),
+            Filter::And(children) => (true, children.as_slice()),
+            Filter::Or(children) => (false, children.as_slice()),
+            Filter::True => (true, &[][..]),
+            Filter::False => (false, &[][..]),
+        };
+
+        let mut members = Vec::with_capacity(children.len());
+        for child in children {
+            match Self::new(child) {
+                Self::Group {
+                    is_and: nested_is_and,
+                    members: nested,
+                } if nested_is_and == is_and => members.extend(nested),
+                other => members.push(other),
+            }
+        }
+
+        let mut keyed = members
+            .into_iter()
+            .map(|member| (member.encode(), member))
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        keyed.dedup_by(|(a, _), (b, _)| a == b);
+
+        if keyed.len() == 1 {
+            return keyed.pop().unwrap().1;
+        }
+
+        Self::Group {
+            is_and,
+            members: keyed.into_iter().map(|(_, member)| member).collect(),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Clause(encoded) => encoded.clone(),
+            Self::Group { is_and, members } => {
+                let mut out = vec![if *is_and { b'&' } else { b'|' }];
+                write_len(&mut out, members.len());
+                for member in members {
+                    out.extend(member.encode());
+                }
+
+                out
+            }
+        }
+    }
+}
+
+fn encode_predicate(predicate: &Predicate) -> Vec<u8> {
+    let mut out = vec![b'c'];
+    write_str(&mut out, &predicate.property.to_string());
+    out.push(match predicate.operator {
+        ClauseOperator::Eq => 0,
+        ClauseOperator::Ne => 1,
+        ClauseOperator::Gt => 2,
+        ClauseOperator::Ge => 3,
+        ClauseOperator::Lt => 4,
+        ClauseOperator::Le => 5,
+        ClauseOperator::Contains => 6,
+        ClauseOperator::NotContains => 7,
+    });
+    out.push(u8::from(predicate.exact));
+    match &predicate.value {
+        Value::Str(s) => {
+            out.push(b's');
+            write_str(&mut out, s);
+        }
+        Value::Int(i) => {
+            out.push(b'i');
+            out.extend(i.to_be_bytes());
+        }
+        Value::Bool(b) => {
+            out.push(b'b');
+            out.push(u8::from(*b));
+        }
+    }
+
+    out
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend((len as u64).to_be_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) {
+    write_len(out, value.len());
+    out.extend(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{filter::filter, Filter};
+
+    #[test]
+    fn ignores_order_parens_and_duplicates() {
+        assert_eq!(
+            filter("a:1+b:2+c:3").fingerprint(),
+            filter("(c:3+(b:2+a:1))+a:1").fingerprint()
+        );
+        assert_eq!(filter("a:1+a:1").fingerprint(), filter("a:1").fingerprint());
+    }
+
+    #[test]
+    fn collapses_single_child_groups() {
+        let nested = Filter::And(vec![filter("c:3"), Filter::Or(vec![filter("a:1+b:2")])]);
+        assert_eq!(nested.fingerprint(), filter("a:1+b:2+c:3").fingerprint());
+
+        let nested = Filter::And(vec![
+            filter("c:3"),
+            Filter::Or(vec![Filter::False, filter("a:1+b:2")]),
+        ]);
+        assert_eq!(nested.fingerprint(), filter("a:1+b:2+c:3").fingerprint());
+    }
+
+    #[test]
+    fn distinguishes_meaningful_changes() {
+        assert_ne!(
+            filter("a:1+b:2").fingerprint(),
+            filter("a:1,b:2").fingerprint()
+        );
+        assert_ne!(filter("a:'1'").fingerprint(), filter("a:1").fingerprint());
+        assert_ne!(
+            filter("a:'x'").fingerprint(),
+            filter("a:['x']").fingerprint()
+        );
+        assert_ne!(filter("a:>1").fingerprint(), filter("a:>=1").fingerprint());
+    }
+
+    /// The fingerprint is documented as stable, so pin a known value.
+    #[test]
+    fn stable_value() {
+        let hex = filter("platform_name:'Windows'+last_seen:>100")
+            .fingerprint()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        expect_test::expect!["94519cec706dd2ffa98168a3c9461efa0119fd23f5c5c3cc75b1931f015ae7e4"]
+            .assert_eq(&hex);
+
+        let hex = Filter::Or(vec![Filter::True, filter("a:1")])
+            .fingerprint()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        expect_test::expect!["c049d67a9704e1133514af7847f50610eb4cc341bfc601f6dd8d475065e42963"]
+            .assert_eq(&hex);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use crate::{semantics, PropertyPath, Value};
+
+use super::Filter;
+
+/// Property values which are known before a record is seen.
+///
+/// A property can be known to have a value, or known to be missing. See
+/// [`Filter::partial_eval`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnownValues {
+    values: BTreeMap<PropertyPath, Option<Value>>,
+}
+
+impl KnownValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that every record has `value` for `property`.
+    pub fn insert(&mut self, property: PropertyPath, value: impl Into<Value>) -> &mut Self {
+        self.values.insert(property, Some(value.into()));
+        self
+    }
+
+    /// Record that no record has a value for `property`.
+    pub fn insert_missing(&mut self, property: PropertyPath) -> &mut Self {
+        self.values.insert(property, None);
+        self
+    }
+
+    /// The known value of `property`, which is `Some(None)` if it is known to be missing.
+    pub fn get(&self, property: &PropertyPath) -> Option<Option<&Value>> {
+        self.values.get(property).map(Option::as_ref)
+    }
+}
+
+impl FromIterator<(PropertyPath, Value)> for KnownValues {
+    fn from_iter<I: IntoIterator<Item = (PropertyPath, Value)>>(iter: I) -> Self {
+        Self {
+            values: iter
+                .into_iter()
+                .map(|(property, value)| (property, Some(value)))
+                .collect(),
+        }
+    }
+}
+
+impl Filter {
+    /// Replace clauses on known properties with [`Filter::True`] or [`Filter::False`],
+    /// and simplify the boolean structure around them.
+    ///
+    /// The result matches the same records as the original filter, for any record
+    /// with the known values. It is `True` or `False` if the known values decide the
+    /// filter by themselves. Clauses on unknown properties are left unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::{Filter, KnownValues};
+    ///
+    /// let filter = fql::parse("platform_name:'Windows'+risk:>50,platform_name:'Mac'")
+    ///     .to_filter()
+    ///     .unwrap();
+    ///
+    /// let mut known = KnownValues::new();
+    /// known.insert("platform_name".parse().unwrap(), "Windows");
+    /// assert_eq!(filter.partial_eval(&known).to_string(), "risk:>50");
+    ///
+    /// known.insert("risk".parse().unwrap(), 10);
+    /// assert_eq!(filter.partial_eval(&known), Filter::False);
+    /// ```
+    pub fn partial_eval(&self, known: &KnownValues) -> Filter {
+        match self {
+            Filter::Clause(predicate) => match known.get(&predicate.property) {
+                Some(value) if semantics::matches(predicate, value) => Filter::True,
+                Some(_) => Filter::False,
+                None => self.clone(),
+            },
+            Filter::True | Filter::False => self.clone(),
+            Filter::And(children) => simplify(children, known, true),
+            Filter::Or(children) => simplify(children, known, false),
+        }
+    }
+}
+
+/// Partially evaluate the children of an `And` if `is_and`, or an `Or` otherwise.
+///
+/// The identity of the operator is dropped, and its absorbing element decides the
+/// whole group.
+fn simplify(children: &[Filter], known: &KnownValues, is_and: bool) -> Filter {
+    let (identity, absorbing) = if is_and {
+        (Filter::True, Filter::False)
+    } else {
+        (Filter::False, Filter::True)
+    };
+
+    let mut remaining = Vec::with_capacity(children.len());
+    for child in children {
+        let child = child.partial_eval(known);
+        if child == absorbing {
+            return absorbing;
+        } else if child != identity {
+            remaining.push(child);
+        }
+    }
+
+    match remaining.len() {
+        0 => identity,
+        1 => remaining.pop().unwrap(),
+        _ if is_and => Filter::And(remaining).flatten(),
+        _ => Filter::Or(remaining).flatten(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Filter, Value};
+
+    use super::KnownValues;
+
+    #[track_caller]
+    fn eval(input: &str, known: &KnownValues) -> Filter {
+        parse(input).to_filter().unwrap().partial_eval(known)
+    }
+
+    fn known(values: &[(&str, Value)]) -> KnownValues {
+        values
+            .iter()
+            .map(|(property, value)| (property.parse().unwrap(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn decides_clauses() {
+        let known = known(&[("os", "Windows".into()), ("risk", 60.into())]);
+        assert_eq!(eval("os:'windows'", &known), Filter::True);
+        assert_eq!(eval("os:['windows']", &known), Filter::False);
+        assert_eq!(eval("os:!'Mac'", &known), Filter::True);
+        assert_eq!(eval("risk:>50+os:'Win*'", &known), Filter::True);
+        assert_eq!(eval("risk:<50,os:~'mac'", &known), Filter::False);
+    }
+
+    #[test]
+    fn keeps_unknown_clauses() {
+        let known = known(&[("os", "Windows".into())]);
+        assert_eq!(eval("os:'Windows'+a:1", &known).to_string(), "a:1");
+        assert_eq!(eval("os:'Mac'+a:1", &known), Filter::False);
+        assert_eq!(eval("os:'Mac',a:1,b:2", &known).to_string(), "a:1,b:2");
+        assert_eq!(eval("os:'Windows',a:1", &known), Filter::True);
+        assert_eq!(
+            eval("a:1+(os:'Mac',b:2+c:3)", &known).to_string(),
+            "a:1+b:2+c:3"
+        );
+        assert_eq!(eval("a:1", &KnownValues::new()).to_string(), "a:1");
+    }
+
+    #[test]
+    fn missing_properties() {
+        let mut known = KnownValues::new();
+        known.insert_missing("os".parse().unwrap());
+        assert_eq!(eval("os:'Windows'", &known), Filter::False);
+        assert_eq!(eval("os:!'Windows'", &known), Filter::True);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::PropertyPath;
+
+use super::Filter;
+
+impl Filter {
+    /// Split the filter into a part a server can apply and a residual to apply locally.
+    ///
+    /// `is_server` decides whether the server supports a property. The returned server
+    /// filter only uses supported properties, and matches at least every record the
+    /// original filter does. Applying the residual to the records the server returns
+    /// gives exactly the records the original filter matches.
+    ///
+    /// Either part may be an empty [`Filter::And`], which matches everything. An `Or`
+    /// which mixes both kinds of property can only be narrowed by the server if every
+    /// branch has a supported clause, and is repeated whole in the residual.
+    ///
+    /// # Example
+    /// ```rust
+    /// let filter = fql::parse("platform_name:'Windows'+risk:>50+(hostname:'a',risk:>90)")
+    ///     .to_filter()
+    ///     .unwrap();
+    ///
+    /// let (server, residual) = filter.split(|path| path.to_string() != "risk");
+    /// assert_eq!(server.to_string(), "platform_name:'Windows'");
+    /// assert_eq!(residual.to_string(), "risk:>50+(hostname:'a',risk:>90)");
+    /// ```
+    pub fn split(&self, is_server: impl Fn(&PropertyPath) -> bool) -> (Filter, Filter) {
+        split(self, &is_server)
+    }
+}
+
+fn split(filter: &Filter, is_server: &dyn Fn(&PropertyPath) -> bool) -> (Filter, Filter) {
+    match filter {
+        Filter::Clause(predicate) if is_server(&predicate.property) => {
+            (filter.clone(), Filter::And(vec![]))
+        }
+        Filter::Clause(_) => (Filter::And(vec![]), filter.clone()),
+        // Neither needs any property, so the server can apply them directly.
+        Filter::True | Filter::False => (filter.clone(), Filter::And(vec![])),
+        // Each child's residual only needs to correct its own server part.
+        Filter::And(children) => {
+            let (server, residual) = children
+                .iter()
+                .map(|child| split(child, is_server))
+                .unzip::<_, _, Vec<_>, Vec<_>>();
+
+            (conjoin(server), conjoin(residual))
+        }
+        // The server can apply the disjunction of each branch's server part, which
+        // is only a restriction if every branch has one. Unless every branch is
+        // handled completely by the server, the residual has to re-check the whole
+        // `Or`, since a record may have matched the server through a different
+        // branch than the one it really matches.
+        Filter::Or(children) => {
+            let (server, residual) = children
+                .iter()
+                .map(|child| split(child, is_server))
+                .unzip::<_, _, Vec<_>, Vec<_>>();
+
+            let residual = if residual.iter().all(is_true) {
+                Filter::And(vec![])
+            } else {
+                filter.clone()
+            };
+
+            (disjoin(server), residual)
+        }
+    }
+}
+
+fn is_true(filter: &Filter) -> bool {
+    match filter {
+        Filter::And(children) => children.is_empty(),
+        Filter::True => true,
+        _ => false,
+    }
+}
+
+/// `And` the filters, dropping any which match everything.
+fn conjoin(filters: Vec<Filter>) -> Filter {
+    let mut children = filters
+        .into_iter()
+        .filter(|f| !is_true(f))
+        .flat_map(|f| match f {
+            Filter::And(nested) => nested,
+            other => vec![other],
+        })
+        .collect::<Vec<_>>();
+
+    if children.len() == 1 {
+        children.pop().unwrap()
+    } else {
+        Filter::And(children)
+    }
+}
+
+/// `Or` the filters, which matches everything if any of them does.
+fn disjoin(filters: Vec<Filter>) -> Filter {
+    if filters.iter().any(is_true) {
+        return Filter::And(vec![]);
+    }
+
+    let mut children = filters
+        .into_iter()
+        .flat_map(|f| match f {
+            Filter::Or(nested) => nested,
+            other => vec![other],
+        })
+        .collect::<Vec<_>>();
+
+    if children.len() == 1 {
+        children.pop().unwrap()
+    } else {
+        Filter::Or(children)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::filter,
+        semantics::{equivalent, implies},
+        Filter,
+    };
+
+    /// Split on properties starting with `s`, checking the parts are sound.
+    #[track_caller]
+    fn check(input: &str) -> (String, String) {
+        let original = filter(input);
+        let (server, residual) = original.split(|path| path.to_string().starts_with('s'));
+
+        assert!(server
+            .predicates()
+            .all(|p| p.property.to_string().starts_with('s')));
+        assert!(implies(&original, &server), "server part is too narrow");
+        let combined = Filter::And(vec![server.clone(), residual.clone()]);
+        assert!(equivalent(&combined, &original).is_equivalent());
+
+        (server.to_string(), residual.to_string())
+    }
+
+    #[test]
+    fn and() {
+        assert_eq!(check("s1:1+c1:2+s2:3"), ("s1:1+s2:3".into(), "c1:2".into()));
+        assert_eq!(check("s1:1+s2:3"), ("s1:1+s2:3".into(), "".into()));
+        assert_eq!(check("c1:1"), ("".into(), "c1:1".into()));
+    }
+
+    #[test]
+    fn or() {
+        assert_eq!(check("s1:1,s2:2"), ("s1:1,s2:2".into(), "".into()));
+        assert_eq!(
+            check("s1:1+c1:2,s2:3"),
+            ("s1:1,s2:3".into(), "s1:1+c1:2,s2:3".into())
+        );
+        assert_eq!(check("s1:1,c1:2"), ("".into(), "s1:1,c1:2".into()));
+        assert_eq!(
+            check("s0:0+(s1:1+c1:1,c2:2)"),
+            ("s0:0".into(), "s1:1+c1:1,c2:2".into())
+        );
+    }
+}
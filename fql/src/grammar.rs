@@ -2,10 +2,12 @@ mod expr;
 mod literal;
 mod operand;
 mod operator;
+mod placeholder;
 mod property;
 
 pub(crate) use expr::expr;
 pub(crate) use literal::literal;
 pub(crate) use operand::operand;
 pub(crate) use operator::operator;
+pub(crate) use placeholder::placeholder;
 pub(crate) use property::property;
@@ -1,6 +1,6 @@
 use crate::{
     lexer::TokenKind,
-    parser::{CompletedMarker, Parser},
+    parser::{CompletedMarker, ParseErrorKind, Parser},
     syntax::SyntaxKind,
 };
 
@@ -38,12 +38,8 @@ pub(crate) fn expr(p: &mut Parser) -> Option<CompletedMarker> {
 fn expr_binding_power(p: &mut Parser, min_power: u8) -> Option<CompletedMarker> {
     let mut lhs = lhs(p)?;
 
-    loop {
-        let (left_power, right_power) = if let Some(op) = CompoundOp::parse(p) {
-            op.binding_power()
-        } else {
-            break;
-        };
+    while let Some(op) = CompoundOp::parse(p) {
+        let (left_power, right_power) = op.binding_power();
 
         if left_power < min_power {
             break;
@@ -68,18 +64,56 @@ fn lhs(p: &mut Parser) -> Option<CompletedMarker> {
 }
 
 fn paren_expr(p: &mut Parser) -> Option<CompletedMarker> {
-    if p.at(TokenKind::LParen) {
-        let m = p.start();
+    if !p.at(TokenKind::LParen) {
+        return None;
+    }
+
+    let m = p.start();
+    let parsed = p.nested(|p| {
         p.bump();
         p.expect_one(expr);
-        p.expect(TokenKind::RParen);
-        Some(m.complete(p, SyntaxKind::ParenExpr))
-    } else {
-        None
+    });
+
+    if parsed.is_none() {
+        let max_depth = p.max_depth();
+        p.report_at_current(ParseErrorKind::NestingTooDeep { max_depth });
+        p.bump();
+        skip_group(p);
     }
+
+    p.expect(TokenKind::RParen);
+    Some(m.complete(p, SyntaxKind::ParenExpr))
+}
+
+/// Consume the body of a group which is nested too deeply, up to its closing
+/// parenthesis, as a single error node.
+///
+/// This tracks nesting with a counter rather than recursion, so arbitrarily deep
+/// input can't overflow the stack.
+fn skip_group(p: &mut Parser) {
+    let m = p.start();
+    let mut open = 0_usize;
+    while let Some(kind) = p.peek() {
+        match kind {
+            TokenKind::LParen => open += 1,
+            TokenKind::RParen if open == 0 => break,
+            TokenKind::RParen => open -= 1,
+            _ => {}
+        }
+
+        p.bump();
+    }
+
+    m.complete(p, SyntaxKind::Error);
 }
 
 fn clause(p: &mut Parser) -> Option<CompletedMarker> {
+    if p.peek() == Some(TokenKind::Ident) {
+        if let Some(halted) = p.start_clause() {
+            return Some(halted);
+        }
+    }
+
     let m = property(p)?.precede(p);
 
     p.expect(TokenKind::Colon);
@@ -282,7 +316,7 @@ mod tests {
                           String@56..65 "'current'"
 
                 At 34..35, expected '.', or ':', found ')'
-                At 34..35, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', boolean, string, or integer, found ')'"#]],
+                At 34..35, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', '$', boolean, string, or integer, found ')'"#]],
         );
     }
 
@@ -317,7 +351,7 @@ mod tests {
                       Ident@5..16 "last_online"
                     Colon@16..17 ":"
 
-                At 16..17, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', boolean, string, or integer"#]],
+                At 16..17, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', '$', boolean, string, or integer"#]],
         );
     }
 
@@ -360,7 +394,7 @@ mod tests {
                   Error@14..18
                     Boolean@14..18 "true"
 
-                At 13..14, expected '[', boolean, string, or integer, found '<'
+                At 13..14, expected '[', '$', boolean, string, or integer, found '<'
                 At 14..18, expected '+', or ',', found boolean"#]],
         )
     }
@@ -382,7 +416,7 @@ mod tests {
                   Error@13..17
                     Boolean@13..17 "true"
 
-                At 12..13, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', boolean, string, or integer, found error
+                At 12..13, expected '!', '>', '<', '>=', '<=', '~', '!~', '[', '$', boolean, string, or integer, found error
                 At 13..17, expected '+', or ',', found boolean"#]],
         )
     }
@@ -473,7 +507,9 @@ mod tests {
 
     #[test]
     fn trailing_operators() {
-        check("host.online:true>>", expect![[r#"
+        check(
+            "host.online:true>>",
+            expect![[r#"
             Root@0..18
               Clause@0..16
                 Property@0..11
@@ -489,6 +525,74 @@ mod tests {
                 Gt@17..18 ">"
 
             At 16..17, expected '+', or ',', found '>'
-            At 17..18, expected nothing, found '>'"#]])
+            At 17..18, expected nothing, found '>'"#]],
+        )
+    }
+
+    #[test]
+    fn nesting_too_deep() {
+        let parse = crate::parse_with_options(
+            "a:1+((b:2)+c:3)",
+            crate::ParseOptions::default().with_max_depth(1),
+        );
+        expect![[r#"
+            Root@0..15
+              InfixExpr@0..15
+                Clause@0..3
+                  Property@0..1
+                    Ident@0..1 "a"
+                  Colon@1..2 ":"
+                  Operand@2..3
+                    Literal@2..3
+                      Integer@2..3 "1"
+                Plus@3..4 "+"
+                ParenExpr@4..15
+                  LParen@4..5 "("
+                  InfixExpr@5..14
+                    ParenExpr@5..10
+                      LParen@5..6 "("
+                      Error@6..9
+                        Ident@6..7 "b"
+                        Colon@7..8 ":"
+                        Integer@8..9 "2"
+                      RParen@9..10 ")"
+                    Plus@10..11 "+"
+                    Clause@11..14
+                      Property@11..12
+                        Ident@11..12 "c"
+                      Colon@12..13 ":"
+                      Operand@13..14
+                        Literal@13..14
+                          Integer@13..14 "3"
+                  RParen@14..15 ")"
+            "#]]
+        .assert_eq(&parse.debug_tree());
+        assert_eq!(
+            parse.error_messages(),
+            ["At 5..6, parentheses are nested too deeply; the maximum depth is 1"]
+        );
+    }
+
+    #[test]
+    fn hostile_nesting() {
+        let input = format!("{}a:1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let parse = crate::parse(&input);
+        assert_eq!(
+            parse.error_messages(),
+            ["At 128..129, parentheses are nested too deeply; the maximum depth is 128"]
+        );
+        assert_eq!(parse.to_expr().unwrap().clauses().count(), 0);
+
+        let unclosed = crate::parse(&"(".repeat(100_000));
+        // One error for the nesting, then one for each group left unclosed.
+        assert_eq!(unclosed.error_messages().len(), 1 + 129);
+    }
+
+    #[test]
+    fn hostile_chain() {
+        let input = vec!["a:1"; 100_000].join("+");
+        let parse = crate::parse(&input);
+        assert!(parse.error_messages().is_empty());
+        assert_eq!(parse.to_expr().unwrap().clauses().count(), 100_000);
     }
 }
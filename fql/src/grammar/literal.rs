@@ -1,6 +1,6 @@
 use crate::{
     lexer::TokenKind,
-    parser::{CompletedMarker, Parser},
+    parser::{CompletedMarker, ParseErrorKind, Parser},
     syntax::SyntaxKind,
 };
 
@@ -10,6 +10,14 @@ const LITERALS: &[TokenKind] = &[TokenKind::Boolean, TokenKind::String, TokenKin
 pub(crate) fn literal(p: &mut Parser) -> Option<CompletedMarker> {
     if p.at_set(LITERALS) {
         let m = p.start();
+        let is_integer = p.peek() == Some(TokenKind::Integer);
+        if is_integer
+            && p.current_text()
+                .and_then(|t| t.parse::<u64>().ok())
+                .is_none()
+        {
+            p.report_at_current(ParseErrorKind::IntegerOutOfRange);
+        }
         p.bump();
         Some(m.complete(p, SyntaxKind::Literal))
     } else {
@@ -69,6 +77,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn integer_out_of_range() {
+        check(
+            "18446744073709551616",
+            expect![[r#"
+                Root@0..20
+                  Literal@0..20
+                    Integer@0..20 "18446744073709551616"
+
+                At 0..20, integer is out of range; the maximum is 18446744073709551615"#]],
+        );
+    }
+
     #[test]
     fn string_empty() {
         check(
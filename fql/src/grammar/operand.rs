@@ -4,7 +4,7 @@ use crate::{
     syntax::SyntaxKind,
 };
 
-use super::literal;
+use super::{literal, placeholder};
 
 pub(crate) fn operand(p: &mut Parser) -> Option<CompletedMarker> {
     if p.at(TokenKind::LBracket) {
@@ -12,11 +12,14 @@ pub(crate) fn operand(p: &mut Parser) -> Option<CompletedMarker> {
         p.bump();
         if p.at(TokenKind::String) {
             literal(p);
+        } else {
+            placeholder(p);
         }
         p.expect(TokenKind::RBracket);
         Some(m.complete(p, SyntaxKind::Operand))
     } else {
-        Some(literal(p)?.precede(p).complete(p, SyntaxKind::Operand))
+        let inner = placeholder(p).or_else(|| literal(p))?;
+        Some(inner.precede(p).complete(p, SyntaxKind::Operand))
     }
 }
 
@@ -52,6 +55,46 @@ mod tests {
         )
     }
 
+    #[test]
+    fn placeholder() {
+        check(
+            "$name",
+            expect![[r#"
+            Root@0..5
+              Operand@0..5
+                Placeholder@0..5
+                  Dollar@0..1 "$"
+                  Ident@1..5 "name""#]],
+        )
+    }
+
+    #[test]
+    fn placeholder_bracketed() {
+        check(
+            "[$name]",
+            expect![[r#"
+            Root@0..7
+              Operand@0..7
+                LBracket@0..1 "["
+                Placeholder@1..6
+                  Dollar@1..2 "$"
+                  Ident@2..6 "name"
+                RBracket@6..7 "]""#]],
+        )
+    }
+
+    #[test]
+    fn string_trailing_backslash() {
+        check(
+            r"'C:\'",
+            expect![[r#"
+                Root@0..5
+                  Operand@0..5
+                    Literal@0..5
+                      String@0..5 "'C:\\'""#]],
+        )
+    }
+
     #[test]
     fn string_bracketed() {
         check(
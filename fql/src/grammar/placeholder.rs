@@ -0,0 +1,51 @@
+use crate::{
+    lexer::TokenKind,
+    parser::{CompletedMarker, Parser},
+    syntax::SyntaxKind,
+};
+
+/// Parses a placeholder, such as `$name`, which stands in for a literal.
+pub(crate) fn placeholder(p: &mut Parser) -> Option<CompletedMarker> {
+    if p.at(TokenKind::Dollar) {
+        let m = p.start();
+        p.bump();
+        p.expect(TokenKind::Ident);
+        Some(m.complete(p, SyntaxKind::Placeholder))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected_tree: Expect) {
+        crate::parser::check_with(super::placeholder, input, expected_tree);
+    }
+
+    #[test]
+    fn named() {
+        check(
+            "$since",
+            expect![[r#"
+                Root@0..6
+                  Placeholder@0..6
+                    Dollar@0..1 "$"
+                    Ident@1..6 "since""#]],
+        );
+    }
+
+    #[test]
+    fn missing_name() {
+        check(
+            "$",
+            expect![[r#"
+                Root@0..1
+                  Placeholder@0..1
+                    Dollar@0..1 "$"
+
+                At 0..1, expected ident"#]],
+        );
+    }
+}
@@ -28,10 +28,12 @@ impl<'a> Iterator for Lexer<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         let kind = self.inner.next()?;
         let text = self.inner.slice();
+        // Offsets past `u32::MAX` can't be represented, so lexing stops there rather
+        // than panicking. The parser rejects such long input before lexing it.
         let range = {
             let std_range = self.inner.span();
-            let start = TextSize::try_from(std_range.start).unwrap();
-            let end = TextSize::try_from(std_range.end).unwrap();
+            let start = TextSize::try_from(std_range.start).ok()?;
+            let end = TextSize::try_from(std_range.end).ok()?;
             TextRange::new(start, end)
         };
 
@@ -98,6 +100,9 @@ pub(crate) enum TokenKind {
     #[token(",")]
     Comma,
 
+    #[token("$")]
+    Dollar,
+
     #[token("(")]
     LParen,
 
@@ -124,6 +129,7 @@ impl fmt::Display for TokenKind {
                 TokenKind::RBracket => "']'",
                 TokenKind::Plus => "'+'",
                 TokenKind::Comma => "','",
+                TokenKind::Dollar => "'$'",
                 TokenKind::LParen => "'('",
                 TokenKind::RParen => "')'",
                 TokenKind::Error => "error",
@@ -227,6 +233,40 @@ mod tests {
         check_seq("!'windows'", vec![(Bang, "!"), (String, "'windows'")])
     }
 
+    /// Strings have no escapes, so a backslash before the closing quote doesn't
+    /// stop the string from ending there.
+    #[test]
+    fn string_trailing_backslash() {
+        use TokenKind::{Colon, Ident, Plus, String};
+
+        check(r"'C:\temp'", String);
+        check_seq(
+            r"'C:\'+n:'x'",
+            vec![
+                (String, r"'C:\'"),
+                (Plus, "+"),
+                (Ident, "n"),
+                (Colon, ":"),
+                (String, "'x'"),
+            ],
+        );
+    }
+
+    #[test]
+    fn placeholder() {
+        use TokenKind::{Colon, Dollar, Ident};
+
+        check_seq(
+            "hostname:$name",
+            vec![
+                (Ident, "hostname"),
+                (Colon, ":"),
+                (Dollar, "$"),
+                (Ident, "name"),
+            ],
+        )
+    }
+
     #[test]
     fn bang_tilde_string() {
         use TokenKind::{BangTilde, String};
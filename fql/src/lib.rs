@@ -1,9 +1,31 @@
 pub mod ast;
+mod bind;
+pub mod compose;
+mod diagnostic;
+mod diff;
+pub mod eval;
+mod filter;
 mod grammar;
 mod lexer;
+pub mod lint;
+mod metrics;
 mod parser;
+mod path;
+pub mod policy;
+mod quote;
+pub mod schema;
+pub mod semantics;
 mod spanned;
 mod syntax;
+mod time;
 
-pub use self::parser::{parse, Parse, ParseError};
+pub use self::ast::Literal;
+pub use self::bind::{BindError, ExpectedType};
+pub use self::diagnostic::{Diagnostic, Fix, Severity, TextEdit};
+pub use self::diff::{diff, Change, Diff, DiffClause};
+pub use self::filter::{Filter, KnownValues, Predicate, Value};
+pub use self::metrics::{CostWeights, Metrics};
+pub use self::parser::{parse, parse_with_options, Parse, ParseError, ParseOptions};
+pub use self::path::{ParsePropertyPathError, PropertyGlob, PropertyPath};
+pub use self::quote::quote_str;
 pub use self::spanned::{Spanned, TextRange, TextSize};
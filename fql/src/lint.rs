@@ -0,0 +1,442 @@
+//! Checks for filters which are valid but probably not what the author intended.
+//!
+//! A [`Linter`] runs a set of [`LintRule`]s over a parse result and reports what they
+//! find as [`Diagnostic`]s tagged with the ID of the rule. Each rule has a default
+//! severity, which can be overridden or turned off with [`Linter::set_level`].
+//!
+//! # Suppression
+//! FQL has no comments, so rules are suppressed with directive lines before the filter
+//! when linting a source document with [`Linter::run_source`]:
+//!
+//! ```text
+//! # fql-allow(broad-wildcard) hostname
+//! # fql-allow(redundant-parens)
+//! hostname:'*'+(online:true)
+//! ```
+//!
+//! A directive lists one or more comma-separated rule IDs, optionally followed by a
+//! property path. With a property path, only diagnostics inside clauses on that
+//! property are suppressed; without one, the rules are suppressed for the whole filter.
+//! Other lines starting with `#` are ignored.
+
+use std::collections::BTreeMap;
+
+use rowan::ast::AstNode;
+
+use crate::{
+    ast::{BooleanContext, Clause, Expr, ExprBinary, Node},
+    Diagnostic, Fix, Parse, PropertyPath, Severity, Spanned, TextRange, TextSize,
+};
+
+mod rules;
+
+pub use self::rules::{
+    BroadWildcard, ContainsNonString, ContradictoryClauses, DeepNesting, DuplicateClause,
+    EmptyString, NegatedExactAlwaysTrue, RedundantParens,
+};
+
+/// A check which looks for a specific problem in a filter.
+///
+/// # Example
+/// ```rust
+/// use fql::{lint::{LintContext, LintRule, Linter}, Spanned};
+///
+/// /// Flag every clause on `cid`, which the server already scopes.
+/// struct NoCid;
+///
+/// impl LintRule for NoCid {
+///     fn id(&self) -> &'static str {
+///         "no-cid"
+///     }
+///
+///     fn check(&self, ctx: &mut LintContext) {
+///         for clause in ctx.clauses() {
+///             if clause.property().is_some_and(|p| p.to_string() == "cid") {
+///                 ctx.report("clauses on 'cid' are redundant", clause.span());
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut linter = Linter::empty();
+/// linter.add_rule(NoCid);
+///
+/// let diagnostics = linter.run(&fql::parse("cid:'abc'+online:true"));
+/// assert_eq!(diagnostics[0].rule(), Some("no-cid"));
+/// ```
+pub trait LintRule {
+    /// A unique, kebab-case identifier for the rule, such as `duplicate-clause`.
+    fn id(&self) -> &'static str;
+
+    /// The severity of the rule's diagnostics, unless the linter is configured otherwise.
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Inspect the filter and report any problems to `ctx`.
+    fn check(&self, ctx: &mut LintContext);
+}
+
+/// The configured level of a lint rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    /// Don't run the rule.
+    Allow,
+    Warning,
+    Error,
+}
+
+/// The filter being linted, and the place lint rules report problems.
+pub struct LintContext<'a> {
+    root: &'a Parse,
+    reports: Vec<(String, TextRange, Option<Fix>)>,
+}
+
+impl<'a> LintContext<'a> {
+    /// The outermost expression of the filter, if there is one.
+    pub fn expr(&self) -> Option<Expr> {
+        self.root.to_expr()
+    }
+
+    /// Every typed node in the filter, in source order, including nodes inside
+    /// incomplete or erroneous input.
+    pub fn nodes(&self) -> impl Iterator<Item = Node> {
+        self.root.syntax().descendants().filter_map(Node::cast)
+    }
+
+    /// Every clause in the filter, in source order.
+    pub fn clauses(&self) -> impl Iterator<Item = Clause> {
+        self.root.syntax().descendants().filter_map(Clause::cast)
+    }
+
+    /// Every chain of operands joined by the same boolean operator, such as the
+    /// `+` chain `a:1`, `(b:2,c:3)`, `d:4` in `a:1+(b:2,c:3)+d:4` and the `,` chain
+    /// inside it.
+    ///
+    /// Each chain is collected once, so rules which compare a clause to its siblings
+    /// should use this rather than calling [`Clause::siblings`] for every clause.
+    pub fn groups(&self) -> impl Iterator<Item = Group> {
+        self.nodes()
+            .filter_map(|node| match node {
+                Node::Binary(binary) => Some(binary),
+                _ => None,
+            })
+            .filter(|binary| binary.context() != BooleanContext::Root && binary.is_chain_root())
+            .map(|binary| Group::new(&binary))
+    }
+
+    /// Report a problem at `range`.
+    pub fn report(&mut self, message: impl Into<String>, range: TextRange) {
+        self.reports.push((message.into(), range, None));
+    }
+
+    /// Report a problem at `range`, with a fix which resolves it.
+    pub fn report_with_fix(&mut self, message: impl Into<String>, range: TextRange, fix: Fix) {
+        self.reports.push((message.into(), range, Some(fix)));
+    }
+}
+
+/// Operands joined by the same boolean operator, from [`LintContext::groups`].
+#[derive(Debug, Clone)]
+pub struct Group {
+    /// The operator joining the operands, which is never [`BooleanContext::Root`].
+    pub context: BooleanContext,
+    /// The operands in source order. Nested chains of the same operator are
+    /// flattened, including through parentheses, and operands of a different operator
+    /// are whole expressions.
+    pub operands: Vec<Expr>,
+}
+
+impl Group {
+    fn new(root: &ExprBinary) -> Self {
+        Self {
+            context: root.context(),
+            operands: root.chain_operands(),
+        }
+    }
+}
+
+/// Runs a configured set of lint rules.
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule + Send + Sync>>,
+    levels: BTreeMap<String, Level>,
+}
+
+impl Linter {
+    /// Create a linter with all the built-in rules at their default severities.
+    pub fn new() -> Self {
+        let mut linter = Self::empty();
+        linter.add_rule(DuplicateClause);
+        linter.add_rule(RedundantParens);
+        linter.add_rule(ContradictoryClauses);
+        linter.add_rule(ContainsNonString);
+        linter.add_rule(EmptyString);
+        linter.add_rule(NegatedExactAlwaysTrue);
+        linter.add_rule(BroadWildcard);
+        linter.add_rule(DeepNesting::default());
+        linter
+    }
+
+    /// Create a linter with no rules.
+    pub fn empty() -> Self {
+        Self {
+            rules: Vec::new(),
+            levels: BTreeMap::new(),
+        }
+    }
+
+    /// Add a rule to the linter. If a rule with the same ID was already added, it is replaced.
+    pub fn add_rule(&mut self, rule: impl LintRule + Send + Sync + 'static) {
+        self.rules.retain(|existing| existing.id() != rule.id());
+        self.rules.push(Box::new(rule));
+    }
+
+    /// Override the level of the rule with ID `rule`.
+    pub fn set_level(&mut self, rule: impl Into<String>, level: Level) {
+        self.levels.insert(rule.into(), level);
+    }
+
+    /// The IDs of the rules in the linter, in the order they run.
+    pub fn rules(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|rule| rule.id())
+    }
+
+    /// Run every enabled rule over `parse`, returning the diagnostics in source order.
+    pub fn run(&self, parse: &Parse) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for rule in &self.rules {
+            let severity = match self.levels.get(rule.id()) {
+                Some(Level::Allow) => continue,
+                Some(Level::Warning) => Severity::Warning,
+                Some(Level::Error) => Severity::Error,
+                None => rule.default_severity(),
+            };
+
+            let mut ctx = LintContext {
+                root: parse,
+                reports: Vec::new(),
+            };
+            rule.check(&mut ctx);
+
+            for (message, range, fix) in ctx.reports {
+                let mut diagnostic = Diagnostic::new(severity, message, range).with_rule(rule.id());
+                if let Some(fix) = fix {
+                    diagnostic = diagnostic.with_fix(fix);
+                }
+
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        diagnostics.sort_by_key(|d| d.span().start());
+        diagnostics
+    }
+
+    /// Lint a source document made of `# fql-allow(...)` directive lines followed by
+    /// a filter. See the [module documentation](self) for the directive syntax.
+    ///
+    /// The ranges of the diagnostics and their fixes are relative to the start of
+    /// `source`, not the start of the filter.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::{lint::Linter, Spanned};
+    ///
+    /// let linter = Linter::new();
+    /// let source = "# fql-allow(broad-wildcard) hostname\nhostname:'*'+tags:'*'";
+    ///
+    /// let diagnostics = linter.run_source(source);
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(&source[diagnostics[0].span()], "'*'");
+    /// assert_eq!(diagnostics[0].span().start(), 55.into());
+    /// ```
+    pub fn run_source(&self, source: &str) -> Vec<Diagnostic> {
+        let mut directives = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut offset = 0;
+        for line in source.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                break;
+            }
+
+            if let Some(directive) = Directive::parse(trimmed) {
+                let range = TextRange::at(
+                    TextSize::try_from(offset).unwrap_or_default(),
+                    TextSize::try_from(line.trim_end().len()).unwrap_or_default(),
+                );
+
+                match directive {
+                    Ok(directive) => {
+                        for rule in &directive.rules {
+                            if !self.rules().any(|id| id == rule) {
+                                diagnostics.push(Diagnostic::warning(
+                                    format!("unknown lint rule '{rule}'"),
+                                    range,
+                                ));
+                            }
+                        }
+
+                        directives.push(directive);
+                    }
+                    Err(message) => diagnostics.push(Diagnostic::warning(message, range)),
+                }
+            }
+
+            offset += line.len();
+        }
+
+        let parse = crate::parse(source[offset..].trim_end());
+        let suppressed_ranges = |property: &PropertyPath| {
+            parse
+                .syntax()
+                .descendants()
+                .filter_map(Clause::cast)
+                .filter(|clause| {
+                    clause
+                        .property()
+                        .is_some_and(|p| PropertyPath::from(p) == *property)
+                })
+                .map(|clause| clause.span())
+                .collect::<Vec<_>>()
+        };
+
+        let suppressions = directives
+            .iter()
+            .map(|d| (d, d.property.as_ref().map(suppressed_ranges)))
+            .collect::<Vec<_>>();
+
+        let shift = TextSize::try_from(offset).unwrap_or_default();
+        diagnostics.extend(
+            self.run(&parse)
+                .into_iter()
+                .filter(|diagnostic| {
+                    !suppressions.iter().any(|(directive, ranges)| {
+                        diagnostic
+                            .rule()
+                            .is_some_and(|rule| directive.rules.iter().any(|r| r == rule))
+                            && ranges.as_ref().is_none_or(|ranges| {
+                                ranges.iter().any(|r| r.contains_range(diagnostic.span()))
+                            })
+                    })
+                })
+                .map(|diagnostic| diagnostic.shift(shift)),
+        );
+
+        diagnostics
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `# fql-allow(...)` line.
+struct Directive {
+    rules: Vec<String>,
+    property: Option<PropertyPath>,
+}
+
+impl Directive {
+    /// Parse a trimmed line, returning `None` if it is not a directive or an error
+    /// message if it is a malformed one.
+    fn parse(line: &str) -> Option<Result<Self, String>> {
+        let rest = line
+            .strip_prefix('#')?
+            .trim_start()
+            .strip_prefix("fql-allow")?;
+        Some(Self::parse_body(rest))
+    }
+
+    fn parse_body(rest: &str) -> Result<Self, String> {
+        let malformed = || {
+            "malformed fql-allow directive; expected `# fql-allow(rule-id) [property]`".to_string()
+        };
+        let (rules, property) = rest
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .ok_or_else(malformed)?;
+
+        let rules = rules
+            .split(',')
+            .map(|rule| rule.trim().to_string())
+            .collect::<Vec<_>>();
+        if rules.iter().any(String::is_empty) {
+            return Err(malformed());
+        }
+
+        let property = match property.trim() {
+            "" => None,
+            property => Some(property.parse().map_err(|e| format!("{e:#}"))?),
+        };
+
+        Ok(Self { rules, property })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Severity, Spanned};
+
+    use super::{Level, Linter};
+
+    #[test]
+    fn levels() {
+        let mut linter = Linter::new();
+        linter.set_level("broad-wildcard", Level::Error);
+        linter.set_level("empty-string", Level::Allow);
+
+        let diagnostics = linter.run(&parse("hostname:'*'+tags:''"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity(), Severity::Error);
+        assert_eq!(diagnostics[0].rule(), Some("broad-wildcard"));
+    }
+
+    #[test]
+    fn suppression() {
+        let linter = Linter::new();
+        let source = "# fql-allow(empty-string, broad-wildcard)\n# A note\nhostname:'*'+tags:''\n";
+        assert!(linter.run_source(source).is_empty());
+
+        let scoped = "# fql-allow(empty-string) tags\nname:''+tags:''";
+        let diagnostics = linter.run_source(scoped);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&scoped[diagnostics[0].span()], "''");
+        assert_eq!(u32::from(diagnostics[0].span().start()), 36);
+    }
+
+    #[test]
+    fn long_chains() {
+        // Rules compare each clause with its whole chain, so this must not take
+        // quadratic time.
+        let clauses = (0..4000)
+            .map(|i| format!("a{i}:!['x']"))
+            .collect::<Vec<_>>();
+        let linter = Linter::new();
+        assert!(linter.run(&parse(&clauses.join("+"))).is_empty());
+        assert!(linter.run(&parse(&clauses.join(","))).is_empty());
+
+        let diagnostics = linter.run(&parse(&format!("{}+a0:!['x']", clauses.join("+"))));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule(), Some("duplicate-clause"));
+    }
+
+    #[test]
+    fn malformed_directives() {
+        let linter = Linter::new();
+        let messages = linter
+            .run_source("# fql-allow(no-such-rule)\n# fql-allow broad-wildcard\na:1")
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "At 0..25, unknown lint rule 'no-such-rule'",
+                "At 26..52, malformed fql-allow directive; expected `# fql-allow(rule-id) [property]`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,553 @@
+//! The lint rules included in [`Linter::new`](super::Linter::new).
+
+use std::collections::{HashMap, HashSet};
+
+use rowan::{ast::AstNode, Direction};
+
+use crate::{
+    ast::{BooleanContext, Clause, ClauseOperator, Expr, ExprParen, Lit, Node},
+    semantics::implies,
+    syntax::SyntaxKind,
+    Filter, Fix, Predicate, PropertyPath, Severity, Spanned, TextEdit, TextRange, Value,
+};
+
+use super::{Group, LintContext, LintRule};
+
+/// `duplicate-clause`: a clause which repeats an earlier clause combined with it by
+/// the same operator, such as the second `a:1` in `a:1+b:2+a:1`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuplicateClause;
+
+impl LintRule for DuplicateClause {
+    fn id(&self) -> &'static str {
+        "duplicate-clause"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for group in ctx.groups().collect::<Vec<_>>() {
+            let mut seen = HashSet::new();
+            for (clause, predicate) in lowered_clauses(&group) {
+                if seen.insert(predicate) {
+                    continue;
+                }
+
+                let message = format!("clause '{clause}' appears more than once");
+                match removal(&Expr::Clause(clause.clone()).outermost_paren()) {
+                    Some(range) => ctx.report_with_fix(
+                        message,
+                        clause.span(),
+                        Fix::replace("Remove the duplicate clause", range, ""),
+                    ),
+                    None => ctx.report(message, clause.span()),
+                }
+            }
+        }
+    }
+}
+
+/// `redundant-parens`: parentheses which don't change how the filter is grouped,
+/// such as those in `(a:1)` or `a:1+(b:2+c:3)`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedundantParens;
+
+impl LintRule for RedundantParens {
+    fn id(&self) -> &'static str {
+        "redundant-parens"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        let parens = ctx
+            .nodes()
+            .filter_map(|node| match node {
+                Node::Paren(paren) => Some(paren),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for paren in parens {
+            let expr = Expr::Paren(paren.clone());
+
+            // Only report the outermost of directly nested parentheses; its fix
+            // leaves the inner ones to be reported next.
+            if matches!(expr.parent_expr(), Some(Expr::Paren(_))) {
+                continue;
+            }
+
+            let Some(body) = paren.body() else {
+                continue;
+            };
+
+            let redundant = match (&body, expr.boolean_context()) {
+                (Expr::Clause(_) | Expr::Paren(_), _) | (_, BooleanContext::Root) => true,
+                (Expr::Binary(binary), context) => {
+                    let op = binary.context();
+                    op == context || (op == BooleanContext::And && context == BooleanContext::Or)
+                }
+            };
+
+            if !redundant {
+                continue;
+            }
+
+            let edits = paren
+                .syntax()
+                .children_with_tokens()
+                .filter_map(|element| element.into_token())
+                .filter(|t| matches!(t.kind(), SyntaxKind::LParen | SyntaxKind::RParen))
+                .map(|t| TextEdit {
+                    range: t.text_range(),
+                    replacement: String::new(),
+                })
+                .collect();
+
+            ctx.report_with_fix(
+                "parentheses are redundant",
+                paren.span(),
+                Fix::new("Remove the parentheses", edits),
+            );
+        }
+    }
+}
+
+/// `contradictory-clauses`: two clauses joined by `+` which no record can match
+/// together, such as `a:1+a:2` or `count:>5+count:<3`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContradictoryClauses;
+
+impl LintRule for ContradictoryClauses {
+    fn id(&self) -> &'static str {
+        "contradictory-clauses"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for group in ctx.groups().collect::<Vec<_>>() {
+            if group.context != BooleanContext::And {
+                continue;
+            }
+
+            // Only clauses on the same property can contradict each other.
+            let mut earlier = HashMap::<PropertyPath, Vec<(Clause, Predicate)>>::new();
+            for (clause, predicate) in lowered_clauses(&group) {
+                // A clause which can't match anything by itself, such as `a:~1`, is left
+                // to other rules rather than reported against each of its siblings.
+                if !satisfiable(&predicate) {
+                    continue;
+                }
+
+                let same_property = earlier.entry(predicate.property.clone()).or_default();
+                let contradiction = same_property.iter().find(|(_, other)| {
+                    let both = Filter::And(vec![other.clone().into(), predicate.clone().into()]);
+                    implies(&both, &Filter::Or(vec![]))
+                });
+
+                if let Some((other, _)) = contradiction {
+                    ctx.report(
+                        format!(
+                            "clause '{clause}' contradicts '{other}', so no record can match both"
+                        ),
+                        clause.span(),
+                    );
+                }
+
+                same_property.push((clause, predicate));
+            }
+        }
+    }
+}
+
+/// `contains-non-string`: `~` or `!~` with an integer or boolean operand, which
+/// the server rejects.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContainsNonString;
+
+impl LintRule for ContainsNonString {
+    fn id(&self) -> &'static str {
+        "contains-non-string"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for clause in ctx.clauses().collect::<Vec<_>>() {
+            let operator = clause.operator_kind();
+            if !matches!(
+                operator,
+                ClauseOperator::Contains | ClauseOperator::NotContains
+            ) {
+                continue;
+            }
+
+            let Some(literal) = clause.operand().and_then(|o| o.literal()) else {
+                continue;
+            };
+
+            let kind = match literal.value() {
+                Some(Lit::Int(_)) => "an integer",
+                Some(Lit::Bool(_)) => "a boolean",
+                _ => continue,
+            };
+
+            ctx.report(
+                format!("'{operator}' only applies to strings, but the operand is {kind}"),
+                literal.span(),
+            );
+        }
+    }
+}
+
+/// `empty-string`: a clause comparing against `''`, which is usually a value
+/// left out by mistake.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptyString;
+
+impl LintRule for EmptyString {
+    fn id(&self) -> &'static str {
+        "empty-string"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for clause in ctx.clauses().collect::<Vec<_>>() {
+            let Some(literal) = clause.operand().and_then(|o| o.literal()) else {
+                continue;
+            };
+
+            if let Some(Lit::Str(s)) = literal.value() {
+                if s.value().is_empty() {
+                    ctx.report("the operand is an empty string", literal.span());
+                }
+            }
+        }
+    }
+}
+
+/// `negated-exact-always-true`: a negated exact match joined by `,` with a clause
+/// which covers every record it rejects, such as `a:!['x'],a:!['y']`, making the
+/// whole group match every record.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NegatedExactAlwaysTrue;
+
+impl NegatedExactAlwaysTrue {
+    fn applies(predicate: &Predicate) -> bool {
+        predicate.exact && predicate.operator.is_negated()
+    }
+}
+
+impl LintRule for NegatedExactAlwaysTrue {
+    fn id(&self) -> &'static str {
+        "negated-exact-always-true"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for group in ctx.groups().collect::<Vec<_>>() {
+            if group.context != BooleanContext::Or {
+                continue;
+            }
+
+            // Only a clause on the same property can cover the records a negated exact
+            // match rejects.
+            let mut by_property = HashMap::<PropertyPath, Vec<(Clause, Predicate)>>::new();
+            for (clause, predicate) in lowered_clauses(&group) {
+                by_property
+                    .entry(predicate.property.clone())
+                    .or_default()
+                    .push((clause, predicate));
+            }
+
+            for clauses in by_property.values() {
+                for (i, (clause, predicate)) in clauses.iter().enumerate() {
+                    if !Self::applies(predicate) {
+                        continue;
+                    }
+
+                    // A pair of negated exact matches is reported once, at the later clause.
+                    let cover = clauses.iter().enumerate().find(|&(j, (_, other))| {
+                        if j == i || (j > i && Self::applies(other)) {
+                            return false;
+                        }
+
+                        let either =
+                            Filter::Or(vec![predicate.clone().into(), other.clone().into()]);
+                        implies(&Filter::And(vec![]), &either)
+                    });
+
+                    if let Some((_, (other, _))) = cover {
+                        ctx.report(
+                            format!("'{clause}' or '{other}' matches every record"),
+                            clause.span(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `broad-wildcard`: a wildcard such as `'*'` which matches any value, so the clause
+/// only checks whether the property is present.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BroadWildcard;
+
+impl LintRule for BroadWildcard {
+    fn id(&self) -> &'static str {
+        "broad-wildcard"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        for clause in ctx.clauses().collect::<Vec<_>>() {
+            if !matches!(
+                clause.operator_kind(),
+                ClauseOperator::Eq | ClauseOperator::Ne
+            ) {
+                continue;
+            }
+
+            let Some(operand) = clause.operand().filter(|o| !o.is_exact()) else {
+                continue;
+            };
+
+            let Some(literal) = operand.literal() else {
+                continue;
+            };
+
+            let broad = matches!(
+                Predicate::lower(&clause).map(|p| p.value),
+                Some(Value::Str(s)) if !s.is_empty() && s.chars().all(|c| c == '*')
+            );
+
+            if broad {
+                let property = clause.property().map(|p| p.to_string()).unwrap_or_default();
+                ctx.report(
+                    format!("{literal} matches any value of '{property}'"),
+                    literal.span(),
+                );
+            }
+        }
+    }
+}
+
+/// `deep-nesting`: parentheses nested more than `max_depth` levels deep, which are
+/// hard to read and usually simplify.
+#[derive(Debug, Clone, Copy)]
+pub struct DeepNesting {
+    pub max_depth: usize,
+}
+
+impl Default for DeepNesting {
+    fn default() -> Self {
+        Self { max_depth: 3 }
+    }
+}
+
+impl LintRule for DeepNesting {
+    fn id(&self) -> &'static str {
+        "deep-nesting"
+    }
+
+    fn check(&self, ctx: &mut LintContext) {
+        let parens = ctx
+            .nodes()
+            .filter_map(|node| match node {
+                Node::Paren(paren) => Some(paren),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Only the parentheses which first exceed the limit are reported, so each
+        // over-nested group produces one diagnostic.
+        for paren in parens {
+            let depth = paren
+                .syntax()
+                .ancestors()
+                .filter(|node| ExprParen::can_cast(node.kind()))
+                .count();
+
+            if depth == self.max_depth + 1 {
+                ctx.report(
+                    format!(
+                        "parentheses are nested {depth} levels deep, more than the limit of {}",
+                        self.max_depth
+                    ),
+                    paren.span(),
+                );
+            }
+        }
+    }
+}
+
+/// Whether any record can match `predicate`.
+fn satisfiable(predicate: &Predicate) -> bool {
+    !implies(&predicate.clone().into(), &Filter::Or(vec![]))
+}
+
+/// The clause `expr` is, looking through parentheses.
+fn as_clause(expr: &Expr) -> Option<Clause> {
+    let mut expr = expr.clone();
+    while let Expr::Paren(paren) = expr {
+        expr = paren.body()?;
+    }
+
+    match expr {
+        Expr::Clause(clause) => Some(clause),
+        _ => None,
+    }
+}
+
+/// The operands of `group` which are clauses, with their lowered predicates, in
+/// source order.
+fn lowered_clauses(group: &Group) -> impl Iterator<Item = (Clause, Predicate)> + '_ {
+    group.operands.iter().filter_map(|operand| {
+        let clause = as_clause(operand)?;
+        let predicate = Predicate::lower(&clause)?;
+        Some((clause, predicate))
+    })
+}
+
+/// The range to delete to remove `expr` along with the operator joining it to the
+/// rest of the filter.
+///
+/// The operator removed is the one directly before `expr`, or directly after it if
+/// `expr` is the first operand, so no other operand is touched.
+fn removal(expr: &Expr) -> Option<TextRange> {
+    let Some(Expr::Binary(_)) = expr.parent_expr() else {
+        return None;
+    };
+
+    let adjacent_op = |direction| {
+        expr.syntax()
+            .siblings_with_tokens(direction)
+            .skip(1)
+            .filter_map(|element| element.into_token())
+            .find(|t| t.kind() == SyntaxKind::Plus || t.kind() == SyntaxKind::Comma)
+    };
+
+    let span = expr.span();
+    if let Some(op) = adjacent_op(Direction::Prev) {
+        Some(TextRange::new(op.text_range().start(), span.end()))
+    } else {
+        let op = adjacent_op(Direction::Next)?;
+        Some(TextRange::new(span.start(), op.text_range().end()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lint::Linter, parse, Spanned};
+
+    use super::{
+        BroadWildcard, ContainsNonString, ContradictoryClauses, DeepNesting, DuplicateClause,
+        EmptyString, NegatedExactAlwaysTrue, RedundantParens,
+    };
+
+    /// Run a single rule over `input`, returning the flagged text and message of each
+    /// diagnostic.
+    #[track_caller]
+    fn check(rule: impl super::LintRule + Send + Sync + 'static, input: &str) -> Vec<String> {
+        let mut linter = Linter::empty();
+        linter.add_rule(rule);
+        linter
+            .run(&parse(input))
+            .iter()
+            .map(|d| format!("{}: {}", &input[d.span()], d.message()))
+            .collect()
+    }
+
+    #[track_caller]
+    fn fixed(rule: impl super::LintRule + Send + Sync + 'static, input: &str) -> Vec<String> {
+        let mut linter = Linter::empty();
+        linter.add_rule(rule);
+        linter
+            .run(&parse(input))
+            .iter()
+            .filter_map(|d| d.fix()?.apply(input))
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_clause() {
+        assert_eq!(
+            check(DuplicateClause, "a:1+b:2+a:1"),
+            ["a:1: clause 'a:1' appears more than once"]
+        );
+        assert_eq!(fixed(DuplicateClause, "a:1+(b:2+a:1)"), ["a:1+(b:2)"]);
+        assert_eq!(fixed(DuplicateClause, "a:1+(a:1)+b:2"), ["a:1+b:2"]);
+        assert_eq!(fixed(DuplicateClause, "a:1+b:2+a:1"), ["a:1+b:2"]);
+        assert_eq!(fixed(DuplicateClause, "a:1,b:2,c:3,b:2"), ["a:1,b:2,c:3"]);
+        assert_eq!(fixed(DuplicateClause, "x:1+y:2+z:3+y:2"), ["x:1+y:2+z:3"]);
+        assert_eq!(fixed(DuplicateClause, "x:1+y:2+y:2+z:3"), ["x:1+y:2+z:3"]);
+        assert!(check(DuplicateClause, "a:1,b:2+a:1").is_empty());
+        assert!(check(DuplicateClause, "a:1+a:[1]").is_empty());
+    }
+
+    #[test]
+    fn redundant_parens() {
+        assert_eq!(fixed(RedundantParens, "(a:1+b:2)"), ["a:1+b:2"]);
+        assert_eq!(fixed(RedundantParens, "a:1+((b:2))"), ["a:1+(b:2)"]);
+        assert_eq!(fixed(RedundantParens, "a:1,(b:2+c:3)"), ["a:1,b:2+c:3"]);
+        assert_eq!(fixed(RedundantParens, "(a:1,b:2),c:3"), ["a:1,b:2,c:3"]);
+        assert!(check(RedundantParens, "a:1+(b:2,c:3)").is_empty());
+    }
+
+    #[test]
+    fn contradictory_clauses() {
+        assert_eq!(
+            check(ContradictoryClauses, "a:1+b:2+a:2"),
+            ["a:2: clause 'a:2' contradicts 'a:1', so no record can match both"]
+        );
+        assert_eq!(check(ContradictoryClauses, "count:>5+count:<3").len(), 1);
+        assert!(check(ContradictoryClauses, "a:1,a:2").is_empty());
+        assert!(check(ContradictoryClauses, "count:>5+count:<9").is_empty());
+        assert!(check(ContradictoryClauses, "a:~1+b:2").is_empty());
+    }
+
+    #[test]
+    fn contains_non_string() {
+        assert_eq!(
+            check(ContainsNonString, "a:~1+b:!~true+c:~'x'"),
+            [
+                "1: '~' only applies to strings, but the operand is an integer",
+                "true: '!~' only applies to strings, but the operand is a boolean",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_string() {
+        assert_eq!(
+            check(EmptyString, "a:''+b:'x'"),
+            ["'': the operand is an empty string"]
+        );
+    }
+
+    #[test]
+    fn negated_exact_always_true() {
+        assert_eq!(
+            check(NegatedExactAlwaysTrue, "a:!['x'],a:!['y']"),
+            ["a:!['y']: 'a:!['y']' or 'a:!['x']' matches every record"]
+        );
+        assert_eq!(check(NegatedExactAlwaysTrue, "a:!['x'],a:['x']").len(), 1);
+        assert!(check(NegatedExactAlwaysTrue, "a:!['x'],b:!['y']").is_empty());
+        assert!(check(NegatedExactAlwaysTrue, "a:!['x']+a:!['y']").is_empty());
+    }
+
+    #[test]
+    fn broad_wildcard() {
+        assert_eq!(
+            check(BroadWildcard, "a:'*'+b:!'**'+c:['*']+d:'x*'"),
+            [
+                "'*': '*' matches any value of 'a'",
+                "'**': '**' matches any value of 'b'",
+            ]
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        assert!(check(DeepNesting::default(), "a:1+(b:2,(c:3+(d:4,e:5)))").is_empty());
+        assert_eq!(
+            check(DeepNesting { max_depth: 1 }, "a:1+(b:2,(c:3+(d:4,e:5)))"),
+            ["(c:3+(d:4,e:5)): parentheses are nested 2 levels deep, more than the limit of 1"]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    ast::{BooleanContext, ClauseOperator, Expr},
+    Parse, Predicate, PropertyPath, Value,
+};
+
+/// Measurements of how expensive a filter is likely to be to evaluate.
+///
+/// See [`Parse::metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Metrics {
+    /// The number of clauses.
+    pub clauses: usize,
+    /// The deepest nesting of boolean groups, as measured by [`Expr::depth`].
+    pub max_depth: usize,
+    /// The most operands joined by a single chain of `,`.
+    pub max_or_fan_out: usize,
+    /// The number of clauses whose string operand contains a `*` wildcard.
+    pub wildcards: usize,
+    /// The number of `~` and `!~` clauses.
+    pub contains: usize,
+    /// The number of different properties the filter refers to.
+    pub distinct_properties: usize,
+}
+
+impl Metrics {
+    /// Estimate the cost of evaluating the filter, as the sum of each metric
+    /// multiplied by its weight.
+    ///
+    /// The score has no unit; it is only meaningful compared to the scores of other
+    /// filters using the same weights.
+    pub fn cost(&self, weights: &CostWeights) -> f64 {
+        self.clauses as f64 * weights.clause
+            + self.max_depth as f64 * weights.depth
+            + self.max_or_fan_out as f64 * weights.or_operand
+            + self.wildcards as f64 * weights.wildcard
+            + self.contains as f64 * weights.contains
+            + self.distinct_properties as f64 * weights.property
+    }
+}
+
+/// The weight of each metric in [`Metrics::cost`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostWeights {
+    pub clause: f64,
+    pub depth: f64,
+    pub or_operand: f64,
+    pub wildcard: f64,
+    pub contains: f64,
+    pub property: f64,
+}
+
+/// Weights which treat substring matches as the most expensive operations, followed
+/// by wildcards, since neither can use an index for an exact lookup.
+impl Default for CostWeights {
+    fn default() -> Self {
+        Self {
+            clause: 1.0,
+            depth: 2.0,
+            or_operand: 0.5,
+            wildcard: 5.0,
+            contains: 10.0,
+            property: 1.0,
+        }
+    }
+}
+
+impl Parse {
+    /// Measure the complexity of the filter.
+    ///
+    /// This only considers the well-formed expression returned by [`Parse::to_expr`],
+    /// so it is most useful for input without diagnostics.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::CostWeights;
+    ///
+    /// let metrics = fql::parse("a:1+(b:~'x',b:'y*',c:3)").metrics();
+    /// assert_eq!(metrics.clauses, 4);
+    /// assert_eq!(metrics.max_depth, 2);
+    /// assert_eq!(metrics.max_or_fan_out, 3);
+    /// assert_eq!(metrics.wildcards, 1);
+    /// assert_eq!(metrics.contains, 1);
+    /// assert_eq!(metrics.distinct_properties, 3);
+    /// assert_eq!(metrics.cost(&CostWeights::default()), 27.5);
+    /// ```
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = Metrics::default();
+        let Some(expr) = self.to_expr() else {
+            return metrics;
+        };
+
+        let mut properties = BTreeSet::<PropertyPath>::new();
+        // The operator of each chain of `+` or `,` found so far, and its number of operands.
+        let mut chains = Vec::<(BooleanContext, usize)>::new();
+
+        // Walk the tree with an explicit stack. Each entry has the depth of the
+        // expression, and the index of the chain it is an operand of, if any.
+        let mut stack = vec![(expr, 0, None::<usize>)];
+        while let Some((expr, depth, chain)) = stack.pop() {
+            match expr {
+                Expr::Paren(paren) => {
+                    stack.extend(paren.body().map(|body| (body, depth, chain)));
+                }
+                Expr::Binary(binary) => {
+                    let context = binary.context();
+                    let (depth, chain) = match chain {
+                        Some(index) if chains[index].0 == context => (depth, index),
+                        _ => {
+                            // This expression is one operand of the enclosing chain,
+                            // and starts a new group one level deeper.
+                            if let Some(outer) = chain {
+                                chains[outer].1 += 1;
+                            }
+
+                            chains.push((context, 0));
+                            (depth + 1, chains.len() - 1)
+                        }
+                    };
+
+                    for operand in [binary.rhs(), binary.lhs()].into_iter().flatten() {
+                        stack.push((operand, depth, Some(chain)));
+                    }
+                }
+                Expr::Clause(clause) => {
+                    if let Some(index) = chain {
+                        chains[index].1 += 1;
+                    }
+
+                    metrics.clauses += 1;
+                    metrics.max_depth = metrics.max_depth.max(depth);
+
+                    if let Some(property) = clause.property() {
+                        properties.insert(property.into());
+                    }
+
+                    if matches!(
+                        clause.operator_kind(),
+                        ClauseOperator::Contains | ClauseOperator::NotContains
+                    ) {
+                        metrics.contains += 1;
+                    } else if Predicate::lower(&clause).is_some_and(|p| {
+                        !p.exact && matches!(&p.value, Value::Str(s) if s.contains('*'))
+                    }) {
+                        metrics.wildcards += 1;
+                    }
+                }
+            }
+        }
+
+        metrics.max_or_fan_out = chains
+            .into_iter()
+            .filter(|(context, _)| *context == BooleanContext::Or)
+            .map(|(_, operands)| operands)
+            .max()
+            .unwrap_or_default();
+        metrics.distinct_properties = properties.len();
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::{CostWeights, Metrics};
+
+    #[test]
+    fn depth_and_fan_out() {
+        let metrics = parse("a:1,(b:2,c:3),(d:4+(e:5,f:6,g:7))").metrics();
+        assert_eq!(metrics.clauses, 7);
+        assert_eq!(metrics.max_depth, 3);
+        assert_eq!(metrics.max_or_fan_out, 4);
+
+        assert_eq!(parse("a:1").metrics().max_depth, 0);
+        assert_eq!(parse("((a:1+b:2))").metrics().max_depth, 1);
+        assert_eq!(parse("a:1+b:2").metrics().max_or_fan_out, 0);
+    }
+
+    #[test]
+    fn matches_expr_depth() {
+        let parse = parse("a:1+(b:2,(c:3+d:4)+e:5),f:6");
+        let expected = parse
+            .to_expr()
+            .unwrap()
+            .clauses()
+            .map(|c| c.depth())
+            .max()
+            .unwrap();
+        assert_eq!(parse.metrics().max_depth, expected);
+    }
+
+    #[test]
+    fn clause_kinds() {
+        let metrics = parse("a:'*'+a:['x*']+b:!~'y'+c:~'z*'+d:1").metrics();
+        assert_eq!(metrics.wildcards, 1);
+        assert_eq!(metrics.contains, 2);
+        assert_eq!(metrics.distinct_properties, 4);
+    }
+
+    #[test]
+    fn cost() {
+        let metrics = Metrics {
+            clauses: 2,
+            contains: 1,
+            ..Metrics::default()
+        };
+        let weights = CostWeights {
+            clause: 3.0,
+            contains: 0.5,
+            ..CostWeights::default()
+        };
+        assert_eq!(metrics.cost(&weights), 6.5);
+        assert_eq!(parse("").metrics(), Metrics::default());
+    }
+}
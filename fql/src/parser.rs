@@ -1,12 +1,15 @@
 use std::mem;
 
-use rowan::{ast::AstNode, GreenNode};
+use rowan::{
+    ast::AstNode, GreenNode, GreenNodeBuilder, Language, NodeOrToken, TextRange, TextSize,
+    TokenAtOffset,
+};
 
 use crate::{
-    ast::Expr,
+    ast::{Expr, Node},
     grammar::expr,
     lexer::{Lexer, Token, TokenKind},
-    syntax::{SyntaxKind, SyntaxNode},
+    syntax::{Fql, SyntaxKind, SyntaxNode, SyntaxToken},
 };
 
 mod error;
@@ -15,6 +18,7 @@ mod sink;
 mod source;
 
 pub use error::ParseError;
+pub(crate) use error::ParseErrorKind;
 pub(crate) use marker::{CompletedMarker, Marker};
 
 use self::{sink::Sink, source::Source};
@@ -32,15 +36,138 @@ pub(crate) enum Event {
 }
 
 pub fn parse(input: &str) -> Parse {
-    parse_with(input, expr)
+    parse_with_options(input, ParseOptions::default())
+}
+
+/// Parse `input`, applying the limits in `options`.
+///
+/// Use this instead of [`parse`] for input from untrusted sources. Exceeding a limit
+/// produces a diagnostic rather than a panic or unbounded work.
+///
+/// # Example
+/// ```rust
+/// use fql::{parse_with_options, ParseOptions};
+///
+/// let options = ParseOptions::default().with_max_depth(1);
+///
+/// let parse = parse_with_options("a:1+((b:2))", options);
+/// assert_eq!(
+///     parse.error_messages(),
+///     ["At 5..6, parentheses are nested too deeply; the maximum depth is 1"]
+/// );
+/// ```
+pub fn parse_with_options(input: &str, options: ParseOptions) -> Parse {
+    parse_with(input, options, expr)
 }
 
+/// Limits applied while parsing, which bound the work done on hostile input.
+///
+/// The defaults only prevent stack overflow and reject input too long to represent;
+/// set tighter limits when parsing filters from untrusted sources.
+///
+/// # Example
+/// ```rust
+/// use fql::{parse_with_options, ParseOptions};
+///
+/// let options = ParseOptions::default().with_max_clauses(2);
+///
+/// let parse = parse_with_options("a:1+b:2+c:3", options);
+/// assert_eq!(
+///     parse.error_messages(),
+///     ["At 8..9, filter has too many clauses; the maximum is 2"]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// The longest input, in bytes, which will be parsed. Longer input is rejected
+    /// without being parsed.
+    ///
+    /// Input longer than `u32::MAX` bytes is always rejected, since offsets into it
+    /// can't be represented.
+    pub max_len: usize,
+    /// The most tokens the input may contain. Input with more is rejected without
+    /// being parsed.
+    pub max_tokens: usize,
+    /// How deeply parentheses may be nested. A group nested any deeper is reported
+    /// and skipped without being parsed.
+    pub max_depth: usize,
+    /// The most clauses the filter may contain. Parsing stops at the first clause
+    /// over the limit.
+    pub max_clauses: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_len: MAX_LEN,
+            max_tokens: usize::MAX,
+            max_depth: 128,
+            max_clauses: usize::MAX,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Set the longest input, in bytes, which will be parsed.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Set the most tokens the input may contain.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set how deeply parentheses may be nested.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the most clauses the filter may contain.
+    pub fn with_max_clauses(mut self, max_clauses: usize) -> Self {
+        self.max_clauses = max_clauses;
+        self
+    }
+}
+
+/// The length of the longest input whose offsets fit in a [`TextSize`].
+const MAX_LEN: usize = u32::MAX as usize;
+
 pub(crate) fn parse_with<F: Fn(&mut Parser) -> Option<CompletedMarker>>(
     input: &str,
+    options: ParseOptions,
     root_parse_fn: F,
 ) -> Parse {
-    let tokens = Lexer::new(input).collect::<Vec<_>>();
-    let parser = Parser::new(&tokens);
+    let max_len = options.max_len.min(MAX_LEN);
+    if input.len() > max_len {
+        // Only the text within the limit is kept, so rejecting a huge input doesn't
+        // copy it. The error is reported where that text ends, so its range is
+        // within the text even if the limit falls inside a character.
+        let mut end = max_len;
+        while !input.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let kept = &input[..end];
+        let range = TextRange::empty(TextSize::of(kept));
+        let kind = ParseErrorKind::InputTooLong { max_len };
+        return Parse::rejected(kept, kind, range);
+    }
+
+    let tokens = Lexer::new(input)
+        .take(options.max_tokens.saturating_add(1))
+        .collect::<Vec<_>>();
+    if tokens.len() > options.max_tokens {
+        let range = tokens[options.max_tokens].range;
+        let max_tokens = options.max_tokens;
+        return Parse::rejected(input, ParseErrorKind::TooManyTokens { max_tokens }, range);
+    }
+
+    let parser = Parser::new(&tokens, options);
     let events = parser.parse_with(root_parse_fn);
     let sink = Sink::new(&tokens, events);
 
@@ -53,14 +180,25 @@ pub(crate) struct Parser<'t, 'input> {
     source: Source<'t, 'input>,
     events: Vec<Event>,
     expected_kinds: Vec<TokenKind>,
+    options: ParseOptions,
+    /// The number of parenthesized groups currently being parsed.
+    depth: usize,
+    /// The number of clauses started so far.
+    clauses: usize,
+    /// Whether parsing stopped early because a limit was exceeded.
+    halted: bool,
 }
 
 impl<'t, 'input> Parser<'t, 'input> {
-    fn new(tokens: &'t [Token<'input>]) -> Self {
+    fn new(tokens: &'t [Token<'input>], options: ParseOptions) -> Self {
         Self {
             source: Source::new(tokens),
             events: vec![],
             expected_kinds: vec![],
+            options,
+            depth: 0,
+            clauses: 0,
+            halted: false,
         }
     }
 
@@ -109,7 +247,7 @@ impl<'t, 'input> Parser<'t, 'input> {
 
     fn at_set_no_expected_kinds(&mut self, kinds: &[TokenKind]) -> bool {
         if let Some(next) = self.peek() {
-            kinds.contains(&&next)
+            kinds.contains(&next)
         } else {
             false
         }
@@ -138,7 +276,7 @@ impl<'t, 'input> Parser<'t, 'input> {
         result
     }
 
-    fn peek(&mut self) -> Option<TokenKind> {
+    pub(super) fn peek(&mut self) -> Option<TokenKind> {
         self.source.peek_kind()
     }
 
@@ -168,6 +306,11 @@ impl<'t, 'input> Parser<'t, 'input> {
     ///
     /// This function does not continue parsing.
     fn report_error(&mut self) {
+        // Once parsing has halted, any further errors are consequences of stopping.
+        if self.halted {
+            return;
+        }
+
         let (found, range) = if let Some(token) = self.source.peek_token() {
             (Some(token.kind), token.range)
         } else {
@@ -175,12 +318,69 @@ impl<'t, 'input> Parser<'t, 'input> {
         };
 
         self.events.push(Event::Error(ParseError {
-            expected: mem::take(&mut self.expected_kinds),
-            found,
+            kind: ParseErrorKind::Unexpected {
+                expected: mem::take(&mut self.expected_kinds),
+                found,
+            },
             range,
         }));
     }
 
+    /// Report a problem with the current token which is not a syntax error.
+    ///
+    /// This function does not consume the token or change the expected kinds.
+    pub(super) fn report_at_current(&mut self, kind: ParseErrorKind) {
+        if let Some(token) = self.source.peek_token() {
+            let range = token.range;
+            self.events.push(Event::Error(ParseError { kind, range }));
+        }
+    }
+
+    /// Run `parse_fn` one level of nesting deeper.
+    ///
+    /// Returns `None` without running `parse_fn` if that would exceed the maximum depth.
+    pub(super) fn nested<T>(&mut self, parse_fn: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        if self.depth >= self.options.max_depth {
+            return None;
+        }
+
+        self.depth += 1;
+        let result = parse_fn(self);
+        self.depth -= 1;
+        Some(result)
+    }
+
+    pub(super) fn max_depth(&self) -> usize {
+        self.options.max_depth
+    }
+
+    /// Count a clause starting at the current token.
+    ///
+    /// If this exceeds the maximum number of clauses, the rest of the input is
+    /// consumed as a single error node, which is returned.
+    pub(super) fn start_clause(&mut self) -> Option<CompletedMarker> {
+        self.clauses += 1;
+        if self.clauses <= self.options.max_clauses {
+            return None;
+        }
+
+        let max_clauses = self.options.max_clauses;
+        self.report_at_current(ParseErrorKind::TooManyClauses { max_clauses });
+        self.halted = true;
+
+        let m = self.start();
+        while !self.at_end() {
+            self.bump();
+        }
+
+        Some(m.complete(self, SyntaxKind::Error))
+    }
+
+    /// The text of the current token, if there is one.
+    pub(super) fn current_text(&mut self) -> Option<&'input str> {
+        self.source.peek_token().map(|token| token.text)
+    }
+
     fn at_end(&mut self) -> bool {
         self.peek().is_none()
     }
@@ -192,12 +392,105 @@ pub struct Parse {
 }
 
 impl Parse {
+    /// The result of rejecting input without parsing it.
+    ///
+    /// The tree holds `text`, which is the input or the part of it within the length
+    /// limit, as a single error token.
+    fn rejected(text: &str, kind: ParseErrorKind, range: TextRange) -> Self {
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(Fql::kind_to_raw(SyntaxKind::Root));
+        if !text.is_empty() {
+            builder.token(Fql::kind_to_raw(SyntaxKind::Error), text);
+        }
+        builder.finish_node();
+
+        Self {
+            green_node: builder.finish(),
+            errors: vec![ParseError { kind, range }],
+        }
+    }
+
     pub fn to_expr(&self) -> Option<Expr> {
         SyntaxNode::new_root(self.green_node.clone())
             .first_child()
             .and_then(Expr::cast)
     }
 
+    /// Assemble a parse from a tree and the errors found in its text.
+    pub(crate) fn from_parts(green_node: GreenNode, errors: Vec<ParseError>) -> Self {
+        Self { green_node, errors }
+    }
+
+    pub(crate) fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green_node.clone())
+    }
+
+    /// The full text which was parsed, including any erroneous input.
+    pub fn syntax_text(&self) -> String {
+        self.syntax().to_string()
+    }
+
+    /// The innermost typed node containing the token at `offset`.
+    ///
+    /// This also finds nodes inside incomplete or erroneous input, such as the
+    /// property of a clause which is missing its operand. See [`Parse::token_at_offset`]
+    /// for how a token is chosen when `offset` is between two tokens.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::ast::Node;
+    ///
+    /// let parse = fql::parse("host.name:'x'");
+    /// assert!(matches!(parse.node_at_offset(2.into()), Some(Node::Property(_))));
+    /// ```
+    pub fn node_at_offset(&self, offset: TextSize) -> Option<Node> {
+        self.token_at_offset(offset)?
+            .parent_ancestors()
+            .find_map(Node::cast)
+    }
+
+    /// The token at `offset`.
+    ///
+    /// When `offset` is on the boundary between two tokens, identifiers and
+    /// literals are preferred over punctuation, and otherwise the token after the
+    /// offset is chosen. This matches where a cursor is usually "in" a word.
+    pub fn token_at_offset(&self, offset: TextSize) -> Option<SyntaxToken> {
+        let root = self.syntax();
+        if !root.text_range().contains_inclusive(offset) {
+            return None;
+        }
+
+        match root.token_at_offset(offset) {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(token) => Some(token),
+            TokenAtOffset::Between(left, right) => {
+                if is_word(&right) || !is_word(&left) {
+                    Some(right)
+                } else {
+                    Some(left)
+                }
+            }
+        }
+    }
+
+    /// The innermost typed node which completely contains `range`.
+    ///
+    /// Returns `None` if `range` is not within the input, or if only the root of the
+    /// tree contains it.
+    pub fn covering_element(&self, range: TextRange) -> Option<Node> {
+        let root = self.syntax();
+        if !root.text_range().contains_range(range) {
+            return None;
+        }
+
+        let node = match root.covering_element(range) {
+            NodeOrToken::Node(node) => node,
+            NodeOrToken::Token(token) => token.parent()?,
+        };
+
+        node.ancestors().find_map(Node::cast)
+    }
+
     pub fn debug_tree(&self) -> String {
         format!("{:#?}", SyntaxNode::new_root(self.green_node.clone()))
     }
@@ -211,6 +504,41 @@ impl Parse {
     }
 }
 
+impl Drop for Parse {
+    /// Free the tree one node at a time.
+    ///
+    /// Dropping a green node drops its children recursively, and a long chain of `+`
+    /// or `,` nests one node per operator, so dropping the tree in one call could
+    /// overflow the stack. Instead, every node is held in a list with its parents
+    /// ahead of its children; each node is then freed while its children are still
+    /// held by the list, so no drop recurses.
+    fn drop(&mut self) {
+        let root = mem::replace(
+            &mut self.green_node,
+            GreenNode::new(Fql::kind_to_raw(SyntaxKind::Root), []),
+        );
+
+        let mut nodes = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            stack.extend(
+                node.children()
+                    .filter_map(NodeOrToken::into_node)
+                    .map(ToOwned::to_owned),
+            );
+            nodes.push(node);
+        }
+    }
+}
+
+/// Whether a token is an identifier or literal, rather than punctuation.
+fn is_word(token: &SyntaxToken) -> bool {
+    matches!(
+        token.kind(),
+        SyntaxKind::Ident | SyntaxKind::String | SyntaxKind::Integer | SyntaxKind::Boolean
+    )
+}
+
 #[cfg(test)]
 pub(crate) fn check(input: &str, expected_tree: expect_test::Expect) {
     check_with(expr, input, expected_tree)
@@ -222,8 +550,8 @@ pub(crate) fn check_with<F: Fn(&mut Parser) -> Option<CompletedMarker>>(
     input: &str,
     expected_tree: expect_test::Expect,
 ) {
-    let parse = parse_with(input, root_parse_fn);
-    let syntax_node = crate::syntax::SyntaxNode::new_root(parse.green_node);
+    let parse = parse_with(input, ParseOptions::default(), root_parse_fn);
+    let syntax_node = parse.syntax();
 
     assert_eq!(
         syntax_node.text_range().len(),
@@ -232,7 +560,7 @@ pub(crate) fn check_with<F: Fn(&mut Parser) -> Option<CompletedMarker>>(
     );
 
     let mut actual_tree = format!("{:#?}", syntax_node);
-    for error in parse.errors {
+    for error in &parse.errors {
         actual_tree.push('\n');
         actual_tree.push_str(&error.to_string());
     }
@@ -243,10 +571,75 @@ pub(crate) fn check_with<F: Fn(&mut Parser) -> Option<CompletedMarker>>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Spanned;
     use expect_test::expect;
 
     #[test]
     fn nothing() {
         check("", expect![[r#"Root@0..0"#]]);
     }
+
+    #[track_caller]
+    fn check_limit(input: &str, options: ParseOptions, expected: &str) {
+        let parse = parse_with_options(input, options);
+        assert_eq!(parse.error_messages(), [expected]);
+
+        // Input over the length limit is only kept up to the last whole character
+        // within the limit.
+        let kept = input
+            .char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .take_while(|&end| end <= options.max_len)
+            .last()
+            .unwrap_or(0);
+        assert_eq!(
+            parse.syntax_text(),
+            input[..kept],
+            "Parse does not cover the input within the limit"
+        );
+        for error in parse.diagnostics() {
+            assert!(usize::from(error.span().end()) <= parse.syntax_text().len());
+        }
+        assert!(parse.to_filter().is_none());
+    }
+
+    #[test]
+    fn limits() {
+        let defaults = ParseOptions::default();
+        check_limit(
+            "a:'long'",
+            defaults.with_max_len(4),
+            "At 4..4, input is too long; the maximum is 4 bytes",
+        );
+        check_limit(
+            "a:'é'",
+            defaults.with_max_len(4),
+            "At 3..3, input is too long; the maximum is 4 bytes",
+        );
+        check_limit(
+            "a:1+b:2",
+            defaults.with_max_tokens(4),
+            "At 4..5, input has too many tokens; the maximum is 4",
+        );
+        check_limit(
+            "a:1+(b:2,(c:3+d:4))",
+            defaults.with_max_clauses(2),
+            "At 10..11, filter has too many clauses; the maximum is 2",
+        );
+        check_limit(
+            "a:1",
+            defaults.with_max_clauses(0),
+            "At 0..1, filter has too many clauses; the maximum is 0",
+        );
+
+        let within = defaults
+            .with_max_len(7)
+            .with_max_tokens(7)
+            .with_max_depth(0)
+            .with_max_clauses(2);
+        assert!(parse_with_options("a:1+b:2", within).to_filter().is_some());
+        assert!(parse_with_options("a:1", defaults.with_max_len(usize::MAX))
+            .to_filter()
+            .is_some());
+    }
 }
@@ -1,41 +1,123 @@
 use std::fmt;
 
-use rowan::TextRange;
+use rowan::{TextRange, TextSize};
 
 use crate::{lexer::TokenKind, Spanned};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseError {
-    pub(super) expected: Vec<TokenKind>,
-    pub(super) found: Option<TokenKind>,
+    pub(super) kind: ParseErrorKind,
     pub(super) range: TextRange,
 }
 
+impl ParseError {
+    /// Move the error `offset` bytes later in the input.
+    pub(crate) fn shift(mut self, offset: TextSize) -> Self {
+        self.range += offset;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ParseErrorKind {
+    /// The parser found a token, or the end of input, where it expected something else.
+    Unexpected {
+        expected: Vec<TokenKind>,
+        found: Option<TokenKind>,
+    },
+    /// An integer literal is too large to be represented as a `u64`.
+    IntegerOutOfRange,
+    /// Parentheses are nested more deeply than [`ParseOptions::max_depth`](crate::ParseOptions::max_depth).
+    NestingTooDeep { max_depth: usize },
+    /// The input is longer than [`ParseOptions::max_len`](crate::ParseOptions::max_len).
+    InputTooLong { max_len: usize },
+    /// The input has more than [`ParseOptions::max_tokens`](crate::ParseOptions::max_tokens) tokens.
+    TooManyTokens { max_tokens: usize },
+    /// The filter has more than [`ParseOptions::max_clauses`](crate::ParseOptions::max_clauses) clauses.
+    TooManyClauses { max_clauses: usize },
+}
+
 /// Display the error.
 ///
 /// The default formatting will include range information, while the alternate form will
 /// omit that data.
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if f.alternate() {
-            write!(f, "Expected ")?;
-        } else {
+        if !f.alternate() {
             write!(
                 f,
-                "At {}..{}, expected ",
+                "At {}..{}, ",
                 u32::from(self.range.start()),
                 u32::from(self.range.end()),
             )?;
         }
 
-        if self.expected.is_empty() {
-            write!(f, "nothing")?;
-        } else {
-            write!(f, "{}", FriendlyList(&self.expected))?;
-        }
+        match &self.kind {
+            ParseErrorKind::Unexpected { expected, found } => {
+                if f.alternate() {
+                    write!(f, "Expected ")?;
+                } else {
+                    write!(f, "expected ")?;
+                }
+
+                if expected.is_empty() {
+                    write!(f, "nothing")?;
+                } else {
+                    write!(f, "{}", FriendlyList(expected))?;
+                }
+
+                if let Some(found) = found {
+                    write!(f, ", found {}", found)?;
+                }
+            }
+            ParseErrorKind::IntegerOutOfRange => {
+                if f.alternate() {
+                    write!(f, "Integer")?;
+                } else {
+                    write!(f, "integer")?;
+                }
 
-        if let Some(found) = self.found {
-            write!(f, ", found {}", found)?;
+                write!(f, " is out of range; the maximum is {}", u64::MAX)?;
+            }
+            ParseErrorKind::NestingTooDeep { max_depth } => {
+                if f.alternate() {
+                    write!(f, "Parentheses")?;
+                } else {
+                    write!(f, "parentheses")?;
+                }
+
+                write!(
+                    f,
+                    " are nested too deeply; the maximum depth is {max_depth}"
+                )?;
+            }
+            ParseErrorKind::InputTooLong { max_len } => {
+                if f.alternate() {
+                    write!(f, "Input")?;
+                } else {
+                    write!(f, "input")?;
+                }
+
+                write!(f, " is too long; the maximum is {max_len} bytes")?;
+            }
+            ParseErrorKind::TooManyTokens { max_tokens } => {
+                if f.alternate() {
+                    write!(f, "Input")?;
+                } else {
+                    write!(f, "input")?;
+                }
+
+                write!(f, " has too many tokens; the maximum is {max_tokens}")?;
+            }
+            ParseErrorKind::TooManyClauses { max_clauses } => {
+                if f.alternate() {
+                    write!(f, "Filter")?;
+                } else {
+                    write!(f, "filter")?;
+                }
+
+                write!(f, " has too many clauses; the maximum is {max_clauses}")?;
+            }
         }
 
         Ok(())
@@ -58,8 +140,8 @@ impl<'a, T: fmt::Display> fmt::Display for FriendlyList<'a, T> {
             1 => write!(f, "{}", items[0]),
             len => {
                 write!(f, "{}", items[0])?;
-                for i in 1..(len - 1) {
-                    write!(f, ", {}", items[i])?;
+                for item in &items[1..(len - 1)] {
+                    write!(f, ", {}", item)?;
                 }
                 write!(f, ", or {}", items[len - 1])
             }
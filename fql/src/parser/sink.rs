@@ -1,6 +1,6 @@
 use std::mem;
 
-use rowan::{GreenNodeBuilder, Language};
+use rowan::{GreenNode, GreenToken, Language, NodeOrToken};
 
 use crate::{
     lexer::Token,
@@ -9,8 +9,18 @@ use crate::{
 
 use super::{Event, Parse, ParseError};
 
+/// Converts parser events into a syntax tree.
+///
+/// This builds green nodes directly rather than through `rowan::GreenNodeBuilder`.
+/// The builder caches and deduplicates nodes with few children, which includes every
+/// binary expression, and rehashing that cache walks each cached node's whole subtree
+/// recursively. For a long chain such as `a:1+a:1+...` that takes quadratic time and
+/// can overflow the stack.
 pub(super) struct Sink<'t, 'input> {
-    builder: GreenNodeBuilder<'static>,
+    /// The kind of each node which has been started but not finished, and the index
+    /// in `children` of its first child.
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<NodeOrToken<GreenNode, GreenToken>>,
     tokens: &'t [Token<'input>],
     cursor: usize,
     events: Vec<Event>,
@@ -20,7 +30,8 @@ pub(super) struct Sink<'t, 'input> {
 impl<'t, 'input> Sink<'t, 'input> {
     pub(super) fn new(tokens: &'t [Token<'input>], events: Vec<Event>) -> Self {
         Self {
-            builder: GreenNodeBuilder::new(),
+            parents: vec![],
+            children: vec![],
             tokens,
             events,
             cursor: 0,
@@ -36,7 +47,7 @@ impl<'t, 'input> Sink<'t, 'input> {
                     forward_parent,
                 } => {
                     if forward_parent.is_none() {
-                        self.builder.start_node(Fql::kind_to_raw(kind));
+                        self.start_node(kind);
                     } else {
                         // Recursively follow the forward_parent fields until they run out,
                         // building a stack of syntax kinds to start.
@@ -63,12 +74,12 @@ impl<'t, 'input> Sink<'t, 'input> {
                         }
 
                         for kind in kinds.into_iter().rev() {
-                            self.builder.start_node(Fql::kind_to_raw(kind));
+                            self.start_node(kind);
                         }
                     }
                 }
                 Event::AddToken => self.token(),
-                Event::FinishNode => self.builder.finish_node(),
+                Event::FinishNode => self.finish_node(),
                 Event::Error(error) => {
                     self.errors.push(error);
                 }
@@ -76,16 +87,36 @@ impl<'t, 'input> Sink<'t, 'input> {
             }
         }
 
+        assert!(
+            self.parents.is_empty(),
+            "every started node should be finished"
+        );
+        let green_node = match self.children.pop() {
+            Some(NodeOrToken::Node(node)) if self.children.is_empty() => node,
+            _ => panic!("events should produce exactly one root node"),
+        };
+
         Parse {
-            green_node: self.builder.finish(),
+            green_node,
             errors: self.errors,
         }
     }
 
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("a node should be started");
+        let children = self.children.drain(first_child..);
+        let node = GreenNode::new(Fql::kind_to_raw(kind), children);
+        self.children.push(node.into());
+    }
+
     fn token(&mut self) {
         let Token { kind, text, .. } = self.tokens[self.cursor];
         self.cursor += 1;
-        self.builder
-            .token(Fql::kind_to_raw(SyntaxKind::from(kind)), text.into());
+        let token = GreenToken::new(Fql::kind_to_raw(SyntaxKind::from(kind)), text);
+        self.children.push(token.into());
     }
 }
@@ -0,0 +1,333 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{
+    ast,
+    lexer::{Lexer, Token, TokenKind},
+};
+
+/// An owned, period-delimited property path, such as `device_policies.prevention.policy_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PropertyPath {
+    segments: Vec<String>,
+}
+
+impl PropertyPath {
+    /// The identifiers which make up the path.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// The final identifier in the path.
+    pub fn last(&self) -> &str {
+        self.segments.last().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Check if `prefix` is equal to the first segments of this path.
+    ///
+    /// Matching is done by whole segments, so `host.name` starts with `host`
+    /// but `hostname` does not.
+    pub fn starts_with(&self, prefix: &PropertyPath) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+
+    /// The path without its final segment, or `None` for a single-segment path.
+    pub fn parent(&self) -> Option<PropertyPath> {
+        if self.segments.len() < 2 {
+            return None;
+        }
+
+        Some(Self {
+            segments: self.segments[..self.segments.len() - 1].to_vec(),
+        })
+    }
+
+    /// Create a new path by appending the segments of `child` to this one.
+    pub fn join(&self, child: &PropertyPath) -> PropertyPath {
+        Self {
+            segments: self
+                .segments
+                .iter()
+                .chain(&child.segments)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Check if the path matches `glob`.
+    pub fn matches(&self, glob: &PropertyGlob) -> bool {
+        glob.matches(self)
+    }
+}
+
+impl fmt::Display for PropertyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.segments.join("."))
+    }
+}
+
+impl FromStr for PropertyPath {
+    type Err = ParsePropertyPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments = s
+            .split('.')
+            .map(|segment| {
+                if is_ident(segment) {
+                    Ok(segment.to_string())
+                } else {
+                    Err(ParsePropertyPathError::new(s, segment))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { segments })
+    }
+}
+
+impl From<&ast::Property> for PropertyPath {
+    fn from(property: &ast::Property) -> Self {
+        Self {
+            segments: property
+                .segments()
+                .map(|segment| segment.text().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl From<ast::Property> for PropertyPath {
+    fn from(property: ast::Property) -> Self {
+        Self::from(&property)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PropertyPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PropertyPath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = std::borrow::Cow::<str>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A pattern which matches property paths segment by segment.
+///
+/// A `*` segment matches exactly one segment of any name, and a `**` segment matches
+/// any number of segments, including none. Other segments must match exactly.
+///
+/// # Example
+/// ```rust
+/// use fql::{PropertyGlob, PropertyPath};
+///
+/// let glob = "device_policies.*.policy_id".parse::<PropertyGlob>().unwrap();
+/// let path = "device_policies.prevention.policy_id".parse::<PropertyPath>().unwrap();
+/// assert!(path.matches(&glob));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PropertyGlob {
+    segments: Vec<GlobSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum GlobSegment {
+    Literal(String),
+    One,
+    Any,
+}
+
+impl PropertyGlob {
+    pub fn matches(&self, path: &PropertyPath) -> bool {
+        Self::matches_from(&self.segments, path.segments())
+    }
+
+    fn matches_from(glob: &[GlobSegment], path: &[String]) -> bool {
+        match glob.split_first() {
+            None => path.is_empty(),
+            Some((GlobSegment::Any, rest)) => {
+                (0..=path.len()).any(|skip| Self::matches_from(rest, &path[skip..]))
+            }
+            Some((segment, rest)) => match path.split_first() {
+                Some((first, path_rest)) => {
+                    let matched = match segment {
+                        GlobSegment::Literal(literal) => literal == first,
+                        _ => true,
+                    };
+
+                    matched && Self::matches_from(rest, path_rest)
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for PropertyGlob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+
+            match segment {
+                GlobSegment::Literal(literal) => f.write_str(literal)?,
+                GlobSegment::One => f.write_str("*")?,
+                GlobSegment::Any => f.write_str("**")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for PropertyGlob {
+    type Err = ParsePropertyPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments = s
+            .split('.')
+            .map(|segment| match segment {
+                "*" => Ok(GlobSegment::One),
+                "**" => Ok(GlobSegment::Any),
+                _ if is_ident(segment) => Ok(GlobSegment::Literal(segment.to_string())),
+                _ => Err(ParsePropertyPathError::new(s, segment)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { segments })
+    }
+}
+
+impl From<PropertyPath> for PropertyGlob {
+    fn from(path: PropertyPath) -> Self {
+        Self {
+            segments: path
+                .segments
+                .into_iter()
+                .map(GlobSegment::Literal)
+                .collect(),
+        }
+    }
+}
+
+/// Check if `segment` would be lexed as a single identifier.
+///
+/// This runs the lexer rather than matching characters here, so keywords such as
+/// `true` are rejected and the two can't disagree about which characters are allowed.
+fn is_ident(segment: &str) -> bool {
+    let mut tokens = Lexer::new(segment);
+    matches!(
+        (tokens.next(), tokens.next()),
+        (Some(Token { kind: TokenKind::Ident, text, .. }), None) if text == segment
+    )
+}
+
+/// An error parsing a [`PropertyPath`] or [`PropertyGlob`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePropertyPathError {
+    input: String,
+    segment: String,
+}
+
+impl ParsePropertyPathError {
+    fn new(input: &str, segment: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            segment: segment.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParsePropertyPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segment.is_empty() {
+            write!(f, "Property path '{}' has an empty segment", self.input)
+        } else {
+            write!(
+                f,
+                "Property path '{}' has invalid segment '{}'",
+                self.input, self.segment
+            )
+        }
+    }
+}
+
+impl Error for ParsePropertyPathError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Expr, parse};
+
+    use super::{PropertyGlob, PropertyPath};
+
+    #[track_caller]
+    fn path(input: &str) -> PropertyPath {
+        input.parse().unwrap()
+    }
+
+    #[track_caller]
+    fn glob(input: &str) -> PropertyGlob {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(path("host.online").segments(), ["host", "online"]);
+        assert!("".parse::<PropertyPath>().is_err());
+        assert!("host..online".parse::<PropertyPath>().is_err());
+        assert!("host.Online".parse::<PropertyPath>().is_err());
+        assert!("host.5".parse::<PropertyPath>().is_err());
+        assert!("true".parse::<PropertyPath>().is_err());
+        assert!("host.false".parse::<PropertyPath>().is_err());
+        assert!("true.host".parse::<PropertyGlob>().is_err());
+        assert_eq!(path("trueish.falsey").segments(), ["trueish", "falsey"]);
+        assert_eq!(
+            "host.".parse::<PropertyPath>().unwrap_err().to_string(),
+            "Property path 'host.' has an empty segment"
+        );
+    }
+
+    #[test]
+    fn from_ast() {
+        let Some(Expr::Clause(clause)) = parse("device.os_version:'11'").to_expr() else {
+            panic!("Expected clause");
+        };
+
+        assert_eq!(
+            PropertyPath::from(clause.property().unwrap()),
+            path("device.os_version")
+        );
+    }
+
+    #[test]
+    fn navigation() {
+        let p = path("device_policies.prevention.policy_id");
+        assert!(p.starts_with(&path("device_policies")));
+        assert!(!p.starts_with(&path("device")));
+        assert_eq!(p.parent(), Some(path("device_policies.prevention")));
+        assert_eq!(path("host").parent(), None);
+        assert_eq!(
+            path("host").join(&path("name.first")),
+            path("host.name.first")
+        );
+        assert_eq!(p.last(), "policy_id");
+        assert_eq!(p.to_string(), "device_policies.prevention.policy_id");
+    }
+
+    #[test]
+    fn globs() {
+        assert!(path("device_policies.prevention.policy_id")
+            .matches(&glob("device_policies.*.policy_id")));
+        assert!(!path("device_policies.policy_id").matches(&glob("device_policies.*.policy_id")));
+        assert!(path("device_policies.policy_id").matches(&glob("device_policies.**")));
+        assert!(path("a.b.c.d").matches(&glob("a.**.d")));
+        assert!(path("a.d").matches(&glob("a.**.d")));
+        assert!(!path("a.b.c").matches(&glob("a.**.d")));
+        assert_eq!(glob("a.*.**").to_string(), "a.*.**");
+    }
+}
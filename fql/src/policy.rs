@@ -0,0 +1,318 @@
+//! Restricting the filters untrusted callers may submit.
+//!
+//! A [`Policy`] limits which properties a filter may refer to, and combines every
+//! filter with a mandatory scope, such as the caller's customer ID. The result of
+//! [`Policy::enforce`] is filter text which can be passed on to a data source
+//! without letting the caller read outside their scope.
+//!
+//! # Example
+//! ```rust
+//! use fql::{policy::Policy, Filter, Predicate};
+//!
+//! let scope = Filter::Clause(Predicate {
+//!     property: "cid".parse().unwrap(),
+//!     operator: fql::ast::ClauseOperator::Eq,
+//!     value: "abc".into(),
+//!     exact: true,
+//! });
+//!
+//! let policy = Policy::new()
+//!     .with_allowed(["hostname".parse().unwrap(), "device.**".parse().unwrap()])
+//!     .with_scope(scope);
+//!
+//! // Without parentheses, `cid:['abc']+hostname:'a',hostname:'b'` would match
+//! // `hostname:'b'` for every customer.
+//! assert_eq!(
+//!     policy.enforce(&fql::parse("hostname:'a',hostname:'b'")).unwrap(),
+//!     "cid:['abc']+(hostname:'a',hostname:'b')"
+//! );
+//!
+//! let error = policy.enforce(&fql::parse("cid:'xyz',hostname:'b'")).unwrap_err();
+//! assert_eq!(error.to_string(), "Property 'cid' is not allowed");
+//! ```
+
+use std::{error::Error, fmt};
+
+use rowan::ast::AstNode;
+
+use crate::{
+    ast::{Clause, Expr},
+    parse_with_options, Filter, KnownValues, Parse, ParseOptions, PropertyGlob, PropertyPath,
+    Spanned, TextRange,
+};
+
+/// The properties a filter may use, and the scope every filter is restricted to.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    allowed: Vec<PropertyGlob>,
+    denied: Vec<PropertyGlob>,
+    scope: Option<Filter>,
+}
+
+impl Policy {
+    /// Create a policy which allows every property and adds no scope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow properties which match at least one of `globs`.
+    ///
+    /// Calling this more than once adds to the list of allowed properties.
+    pub fn with_allowed(mut self, globs: impl IntoIterator<Item = PropertyGlob>) -> Self {
+        self.allowed.extend(globs);
+        self
+    }
+
+    /// Reject properties which match any of `globs`, even if they are also allowed.
+    pub fn with_denied(mut self, globs: impl IntoIterator<Item = PropertyGlob>) -> Self {
+        self.denied.extend(globs);
+        self
+    }
+
+    /// Require every record matched by an enforced filter to also match `scope`.
+    ///
+    /// The scope is trusted, so its properties aren't checked against the allowed and
+    /// denied properties. [`Filter::True`] and [`Filter::False`] inside it are
+    /// simplified away. A scope which matches nothing, such as `False` or an empty
+    /// [`Filter::Or`], has no FQL equivalent, so enforcing it fails with
+    /// [`PolicyError::InvalidScope`].
+    pub fn with_scope(mut self, scope: Filter) -> Self {
+        self.scope = Some(scope.partial_eval(&KnownValues::new()));
+        self
+    }
+
+    /// Whether filters may refer to `property`.
+    pub fn allows(&self, property: &PropertyPath) -> bool {
+        (self.allowed.is_empty() || self.allowed.iter().any(|glob| glob.matches(property)))
+            && !self.denied.iter().any(|glob| glob.matches(property))
+    }
+
+    /// Check `parse` against the policy, and return its text combined with the scope.
+    ///
+    /// The caller's filter is wrapped in parentheses when it contains a boolean
+    /// operator, so no part of it can escape the scope. An empty filter is replaced by
+    /// the scope alone. Without a scope, the filter is returned unchanged.
+    ///
+    /// # Errors
+    /// Enforcement fails if the filter has syntax errors, since its structure can't be
+    /// trusted, or if any clause refers to a property the policy doesn't allow. It
+    /// also fails if the filter was parsed with a higher nesting limit than the
+    /// default, and is nested too deeply to wrap in parentheses within it.
+    pub fn enforce(&self, parse: &Parse) -> Result<String, PolicyError> {
+        if let Some(error) = parse.diagnostics().next() {
+            return Err(PolicyError::Syntax { span: error.span() });
+        }
+
+        for clause in parse.syntax().descendants().filter_map(Clause::cast) {
+            let Some(property) = clause.property() else {
+                continue;
+            };
+
+            let path = PropertyPath::from(&property);
+            if !self.allows(&path) {
+                return Err(PolicyError::Forbidden {
+                    property: path,
+                    span: property.span(),
+                });
+            }
+        }
+
+        let expr = parse.to_expr();
+        let scope = match &self.scope {
+            Some(Filter::False) => return Err(PolicyError::InvalidScope),
+            Some(Filter::Or(children)) if children.len() > 1 => {
+                format!("({})", Filter::Or(children.clone()))
+            }
+            Some(scope) => scope.to_string(),
+            None => String::new(),
+        };
+
+        // The scope is written from a `Filter` rather than checked text, so make sure
+        // it is valid before trusting it.
+        if crate::parse(&scope).diagnostics().next().is_some() {
+            return Err(PolicyError::InvalidScope);
+        }
+
+        let hardened = match expr {
+            _ if scope.is_empty() => return Ok(parse.syntax_text()),
+            None => scope,
+            Some(expr @ Expr::Binary(_)) => format!("{scope}+({expr})"),
+            Some(expr) => format!("{scope}+{expr}"),
+        };
+
+        // Wrapping the filter in parentheses nests it one level deeper than it was
+        // parsed at, so allow for that when checking the result.
+        let defaults = ParseOptions::default();
+        let options = defaults.with_max_depth(defaults.max_depth.saturating_add(1));
+        if parse_with_options(&hardened, options)
+            .diagnostics()
+            .next()
+            .is_some()
+        {
+            return Err(PolicyError::TooDeep);
+        }
+
+        Ok(hardened)
+    }
+}
+
+/// An error from [`Policy::enforce`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyError {
+    /// The filter has a syntax error at `span`.
+    Syntax { span: TextRange },
+    /// The property at `span` is not allowed by the policy.
+    Forbidden {
+        property: PropertyPath,
+        span: TextRange,
+    },
+    /// The policy's scope can't be written as valid FQL, such as a scope which
+    /// matches nothing.
+    InvalidScope,
+    /// The filter is nested too deeply to wrap in parentheses within the default
+    /// [`ParseOptions::max_depth`].
+    TooDeep,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Syntax { span } => write!(
+                f,
+                "Filter has a syntax error at {}..{}",
+                u32::from(span.start()),
+                u32::from(span.end())
+            ),
+            PolicyError::Forbidden { property, .. } => {
+                write!(f, "Property '{property}' is not allowed")
+            }
+            PolicyError::InvalidScope => f.write_str("Policy scope can't be written as FQL"),
+            PolicyError::TooDeep => {
+                f.write_str("Filter is nested too deeply to combine with the policy scope")
+            }
+        }
+    }
+}
+
+impl Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{filter::filter, parse, parse_with_options, Filter, ParseOptions, PropertyGlob};
+
+    use super::{Policy, PolicyError};
+
+    #[track_caller]
+    fn glob(input: &str) -> PropertyGlob {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn allow_and_deny() {
+        let policy = Policy::new()
+            .with_allowed([glob("device.**"), glob("hostname")])
+            .with_denied([glob("device.secret")]);
+
+        assert!(policy.allows(&"device.os.version".parse().unwrap()));
+        assert!(policy.allows(&"hostname".parse().unwrap()));
+        assert!(!policy.allows(&"device.secret".parse().unwrap()));
+        assert!(!policy.allows(&"cid".parse().unwrap()));
+
+        assert!(Policy::new().allows(&"cid".parse().unwrap()));
+    }
+
+    #[test]
+    fn forbidden_inside_groups() {
+        let policy = Policy::new().with_denied([glob("cid")]);
+        assert_eq!(
+            policy.enforce(&parse("a:1+(b:2,cid:'x')")),
+            Err(PolicyError::Forbidden {
+                property: "cid".parse().unwrap(),
+                span: crate::TextRange::new(9.into(), 12.into()),
+            })
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        let policy = Policy::new().with_scope(filter("cid:['a'],cid:['b']"));
+        assert_eq!(
+            policy.enforce(&parse("x:1,y:2")).unwrap(),
+            "(cid:['a'],cid:['b'])+(x:1,y:2)"
+        );
+        assert_eq!(
+            policy.enforce(&parse("(x:1,y:2)")).unwrap(),
+            "(cid:['a'],cid:['b'])+(x:1,y:2)"
+        );
+        assert_eq!(policy.enforce(&parse("")).unwrap(), "(cid:['a'],cid:['b'])");
+
+        let policy = Policy::new().with_scope(filter("cid:['a']+online:true"));
+        assert_eq!(
+            policy.enforce(&parse("x:1")).unwrap(),
+            "cid:['a']+online:true+x:1"
+        );
+
+        assert_eq!(Policy::new().enforce(&parse("x:1,y:2")).unwrap(), "x:1,y:2");
+    }
+
+    #[test]
+    fn simplifies_scope_constants() {
+        let policy = Policy::new().with_scope(Filter::And(vec![Filter::True, filter("cid:['a']")]));
+        assert_eq!(policy.enforce(&parse("a:1")).unwrap(), "cid:['a']+a:1");
+
+        let policy = Policy::new().with_scope(Filter::Or(vec![Filter::False, filter("cid:['a']")]));
+        assert_eq!(
+            policy.enforce(&parse("a:1,b:2")).unwrap(),
+            "cid:['a']+(a:1,b:2)"
+        );
+    }
+
+    #[test]
+    fn rejects_scopes_which_match_nothing() {
+        for scope in [
+            Filter::False,
+            Filter::Or(vec![]),
+            Filter::And(vec![Filter::False, filter("cid:['a']")]),
+        ] {
+            let policy = Policy::new().with_scope(scope);
+            assert_eq!(
+                policy.enforce(&parse("a:1")),
+                Err(PolicyError::InvalidScope)
+            );
+            assert_eq!(policy.enforce(&parse("")), Err(PolicyError::InvalidScope));
+        }
+    }
+
+    #[test]
+    fn rejects_syntax_errors() {
+        let policy = Policy::new().with_scope(filter("cid:['a']"));
+        assert!(matches!(
+            policy.enforce(&parse("x:1),y:2")),
+            Err(PolicyError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn deeply_nested_filter() {
+        let nested = |depth: usize| format!("b:1+{}a:1{}", "(".repeat(depth), ")".repeat(depth));
+
+        let policy = Policy::new().with_scope(filter("cid:['a']"));
+        let input = parse(&nested(ParseOptions::default().max_depth));
+        assert!(input.error_messages().is_empty());
+        let hardened = policy.enforce(&input).unwrap();
+        assert!(hardened.starts_with("cid:['a']+(b:1+(("));
+
+        let options = ParseOptions::default().with_max_depth(200);
+        let input = parse_with_options(&nested(150), options);
+        assert!(input.error_messages().is_empty());
+        assert_eq!(policy.enforce(&input), Err(PolicyError::TooDeep));
+    }
+
+    #[test]
+    fn hardened_filter_stays_in_scope() {
+        let scope = filter("cid:['a']");
+        let policy = Policy::new().with_scope(scope.clone());
+        let hardened = policy.enforce(&parse("x:1,y:2+z:3")).unwrap();
+        assert!(crate::semantics::implies(&filter(&hardened), &scope));
+    }
+}
@@ -0,0 +1,31 @@
+/// Write `value` as an FQL string literal, surrounded by single quotes.
+///
+/// FQL strings have no escapes: a string ends at the next single quote, and every
+/// other character, including a backslash, stands for itself. A value containing a
+/// single quote therefore can't be written as a literal, and this returns `None`.
+///
+/// # Example
+/// ```rust
+/// assert_eq!(fql::quote_str(r"C:\temp").as_deref(), Some(r"'C:\temp'"));
+/// assert_eq!(fql::quote_str("O'Brien"), None);
+/// ```
+pub fn quote_str(value: &str) -> Option<String> {
+    if value.contains('\'') {
+        return None;
+    }
+
+    Some(format!("'{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quote_str;
+
+    #[test]
+    fn quote() {
+        assert_eq!(quote_str("").as_deref(), Some("''"));
+        assert_eq!(quote_str("plain").as_deref(), Some("'plain'"));
+        assert_eq!(quote_str(r"C:\").as_deref(), Some(r"'C:\'"));
+        assert_eq!(quote_str("it's"), None);
+    }
+}
@@ -0,0 +1,721 @@
+//! Validation of filters against the properties a data source supports.
+//!
+//! A filter can be syntactically valid and still meaningless: `hostnmae:'x'` parses,
+//! but silently matches nothing if the records have no `hostnmae` property. A [`Schema`]
+//! describes the properties which exist, the type of value each holds, and the
+//! operators each supports, and reports clauses which don't fit as [`Diagnostic`]s.
+//!
+//! # File format
+//! With the `serde` feature enabled, a `Schema` can be deserialized from any
+//! self-describing format. The format is described by the JSON Schema in
+//! `fql-schema.json` at the root of this crate; in JSON it looks like this:
+//!
+//! ```json
+//! {
+//!     "$schema": "./fql-schema.json",
+//!     "properties": {
+//!         "hostname": { "type": "string", "aliases": ["host_name"] },
+//!         "last_seen": { "type": "timestamp", "description": "When the device last checked in." },
+//!         "platform_name": { "type": "enum", "values": ["Windows", "Mac", "Linux"] },
+//!         "agent_version": {
+//!             "type": "integer",
+//!             "operators": ["eq", "gt", "ge", "lt", "le"],
+//!             "deprecated": { "note": "Removed in v2.", "replacement": "agent.version" }
+//!         },
+//!         "agent.version": { "type": "string" }
+//!     }
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fmt};
+
+use rowan::ast::AstNode;
+
+use crate::{
+    ast::{Clause, ClauseOperator, Lit},
+    semantics,
+    time::is_valid_date,
+    Diagnostic, Fix, Parse, Predicate, PropertyPath, Spanned, Value,
+};
+
+#[cfg(feature = "serde")]
+mod de;
+mod rewrite;
+mod suggest;
+
+/// The properties a data source supports.
+///
+/// # Example
+/// ```rust
+/// use fql::schema::{PropertySchema, Schema, ValueType};
+///
+/// let mut schema = Schema::new();
+/// schema.insert("hostname".parse().unwrap(), PropertySchema::new(ValueType::String));
+/// schema.insert("online".parse().unwrap(), PropertySchema::new(ValueType::Bool));
+///
+/// assert!(schema.validate(&fql::parse("hostname:~'web'+online:true")).is_empty());
+///
+/// let diagnostics = schema.validate(&fql::parse("hostnmae:'x'+online:'yes'"));
+/// assert_eq!(
+///     diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+///     [
+///         "At 0..8, unknown property 'hostnmae'; did you mean 'hostname'?",
+///         "At 20..25, property 'online' expects a boolean, found a string",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    properties: BTreeMap<PropertyPath, PropertySchema>,
+    /// The canonical path of each alias declared by a property.
+    aliases: BTreeMap<PropertyPath, PropertyPath>,
+}
+
+impl Schema {
+    /// Create a schema with no properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a property to the schema, returning the previous definition of the path if
+    /// there was one.
+    ///
+    /// The aliases of the previous definition are removed, and the aliases of `property`
+    /// are registered in their place.
+    pub fn insert(
+        &mut self,
+        path: PropertyPath,
+        property: PropertySchema,
+    ) -> Option<PropertySchema> {
+        self.aliases.retain(|_, canonical| *canonical != path);
+        for alias in &property.aliases {
+            self.aliases.insert(alias.clone(), path.clone());
+        }
+
+        self.properties.insert(path, property)
+    }
+
+    /// The definition of the property at `path`.
+    ///
+    /// This does not resolve aliases; use [`Schema::resolve`] for that.
+    pub fn get(&self, path: &PropertyPath) -> Option<&PropertySchema> {
+        self.properties.get(path)
+    }
+
+    /// The canonical path and definition of the property at `path`, which may be
+    /// either the property's own path or one of its aliases.
+    pub fn resolve(&self, path: &PropertyPath) -> Option<(&PropertyPath, &PropertySchema)> {
+        let canonical = self.aliases.get(path).unwrap_or(path);
+        self.properties.get_key_value(canonical)
+    }
+
+    /// Iterate through the properties in the schema, sorted by path.
+    pub fn properties(&self) -> impl Iterator<Item = (&PropertyPath, &PropertySchema)> {
+        self.properties.iter()
+    }
+
+    /// Check every clause in `parse` against the schema.
+    ///
+    /// Clauses inside incomplete or erroneous input are still checked, so this can run
+    /// alongside [`Parse::diagnostics`]. Parts of a clause which are missing, and
+    /// values supplied by placeholders, are not reported. Diagnostics are returned in
+    /// source order.
+    pub fn validate(&self, parse: &Parse) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for clause in parse.syntax().descendants().filter_map(Clause::cast) {
+            self.validate_clause(&clause, &mut diagnostics);
+        }
+
+        diagnostics
+    }
+
+    fn validate_clause(&self, clause: &Clause, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(property) = clause.property() else {
+            return;
+        };
+
+        let path = PropertyPath::from(&property);
+        let Some((canonical, schema)) = self.resolve(&path) else {
+            let diagnostic = match self.suggest(&path) {
+                Some(suggestion) => Diagnostic::error(
+                    format!("unknown property '{path}'; did you mean '{suggestion}'?"),
+                    property.span(),
+                )
+                .with_fix(Fix::replace(
+                    format!("Replace with '{suggestion}'"),
+                    property.span(),
+                    suggestion.to_string(),
+                )),
+                None => Diagnostic::error(format!("unknown property '{path}'"), property.span()),
+            };
+
+            diagnostics.push(diagnostic);
+            return;
+        };
+
+        let replace = |preferred: &PropertyPath| {
+            Fix::replace(
+                format!("Replace with '{preferred}'"),
+                property.span(),
+                preferred.to_string(),
+            )
+        };
+
+        if let Some(deprecation) = schema.deprecation() {
+            let mut message = format!("property '{canonical}' is deprecated");
+            if let Some(note) = &deprecation.note {
+                message = format!("{message}; {note}");
+            }
+
+            let preferred = self.preferred(&path);
+            if let Some(preferred) = &preferred {
+                message = format!("{message}; use '{preferred}' instead");
+            }
+
+            let mut diagnostic = Diagnostic::warning(message, property.span());
+            if let Some(preferred) = &preferred {
+                diagnostic = diagnostic.with_fix(replace(preferred));
+            }
+
+            diagnostics.push(diagnostic);
+        } else if *canonical != path {
+            diagnostics.push(
+                Diagnostic::warning(
+                    format!("property '{path}' is an alias of '{canonical}'"),
+                    property.span(),
+                )
+                .with_fix(replace(canonical)),
+            );
+        }
+
+        let operator = clause.operator_kind();
+        if !schema.allows(operator) {
+            let range = clause
+                .operator()
+                .map(|token| token.text_range())
+                .unwrap_or_else(|| clause.span());
+            let message = if operator == ClauseOperator::Eq {
+                format!("property '{path}' does not support equality comparisons")
+            } else {
+                format!("property '{path}' does not support the '{operator}' operator")
+            };
+
+            diagnostics.push(Diagnostic::error(message, range));
+        }
+
+        let Some(operand) = clause.operand() else {
+            return;
+        };
+
+        if operand.is_exact() && !schema.exact {
+            diagnostics.push(Diagnostic::error(
+                format!("property '{path}' does not support exact matches"),
+                operand.span(),
+            ));
+        }
+
+        let Some(value) = operand.literal().and_then(|literal| literal.value()) else {
+            return;
+        };
+
+        let value = match value {
+            Lit::Str(s) => Value::Str(s.value().into_owned()),
+            Lit::Bool(b) => Value::Bool(b.value()),
+            Lit::Int(i) => match i.value() {
+                Ok(v) => Value::Int(v),
+                // Out-of-range integers are already reported by the parser.
+                Err(_) if schema.value_type == ValueType::Int => return,
+                // Only the type matters when checking against other value types.
+                Err(_) => Value::Int(u64::MAX),
+            },
+        };
+
+        let predicate = Predicate {
+            property: path,
+            operator,
+            value,
+            exact: operand.is_exact(),
+        };
+
+        if let Some(message) = schema.value_type.check(&predicate) {
+            diagnostics.push(Diagnostic::error(message, operand.span()));
+        }
+    }
+}
+
+impl FromIterator<(PropertyPath, PropertySchema)> for Schema {
+    fn from_iter<T: IntoIterator<Item = (PropertyPath, PropertySchema)>>(iter: T) -> Self {
+        let mut schema = Self::new();
+        for (path, property) in iter {
+            schema.insert(path, property);
+        }
+
+        schema
+    }
+}
+
+/// The definition of a single property in a [`Schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertySchema {
+    value_type: ValueType,
+    operators: Vec<ClauseOperator>,
+    exact: bool,
+    description: Option<String>,
+    deprecation: Option<Deprecation>,
+    aliases: Vec<PropertyPath>,
+}
+
+impl PropertySchema {
+    /// Create a property which supports the default operators for its type.
+    ///
+    /// Every type supports `:` and `:!`. Strings also support `~` and `!~`, and
+    /// strings, integers, and timestamps support the range operators. Exact matches
+    /// with `[...]` are supported for strings and enums.
+    pub fn new(value_type: ValueType) -> Self {
+        use ClauseOperator::*;
+
+        let operators = match value_type {
+            ValueType::String => vec![Eq, Ne, Gt, Ge, Lt, Le, Contains, NotContains],
+            ValueType::Int | ValueType::Timestamp => vec![Eq, Ne, Gt, Ge, Lt, Le],
+            ValueType::Bool | ValueType::Enum(_) => vec![Eq, Ne],
+        };
+
+        let exact = matches!(value_type, ValueType::String | ValueType::Enum(_));
+
+        Self {
+            value_type,
+            operators,
+            exact,
+            description: None,
+            deprecation: None,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Replace the operators the property supports.
+    pub fn with_operators(mut self, operators: impl IntoIterator<Item = ClauseOperator>) -> Self {
+        self.operators = operators.into_iter().collect();
+        self
+    }
+
+    /// Set whether the property supports exact matches with `[...]`.
+    pub fn with_exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Set a human-readable description of the property.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Mark the property as deprecated.
+    pub fn with_deprecation(mut self, deprecation: Deprecation) -> Self {
+        self.deprecation = Some(deprecation);
+        self
+    }
+
+    /// Set other paths which refer to this property.
+    pub fn with_aliases(mut self, aliases: impl IntoIterator<Item = PropertyPath>) -> Self {
+        self.aliases = aliases.into_iter().collect();
+        self
+    }
+
+    pub fn value_type(&self) -> &ValueType {
+        &self.value_type
+    }
+
+    pub fn operators(&self) -> &[ClauseOperator] {
+        &self.operators
+    }
+
+    /// Whether the property supports `operator`.
+    pub fn allows(&self, operator: ClauseOperator) -> bool {
+        self.operators.contains(&operator)
+    }
+
+    /// Whether the property supports exact matches with `[...]`.
+    pub fn supports_exact(&self) -> bool {
+        self.exact
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Details of the deprecation, if the property is deprecated.
+    pub fn deprecation(&self) -> Option<&Deprecation> {
+        self.deprecation.as_ref()
+    }
+
+    pub fn aliases(&self) -> &[PropertyPath] {
+        &self.aliases
+    }
+}
+
+/// Details about a deprecated property.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    /// An explanation of the deprecation, such as when the property will be removed.
+    pub note: Option<String>,
+    /// The property which should be used instead.
+    pub replacement: Option<PropertyPath>,
+}
+
+/// The type of value a property holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    String,
+    Int,
+    Bool,
+    /// A string holding an RFC 3339 date or date-time, such as `'2022-04-01T12:30:00Z'`,
+    /// or a time relative to the present, such as `'now-7d'`.
+    Timestamp,
+    /// A string which must be one of the listed values.
+    Enum(Vec<String>),
+}
+
+impl ValueType {
+    /// Check the value of `predicate` against the type, returning an error message if
+    /// it does not fit.
+    fn check(&self, predicate: &Predicate) -> Option<String> {
+        let path = &predicate.property;
+        let mismatch = || {
+            format!(
+                "property '{path}' expects {self}, found {}",
+                match predicate.value {
+                    Value::Str(_) => "a string",
+                    Value::Int(_) => "an integer",
+                    Value::Bool(_) => "a boolean",
+                }
+            )
+        };
+
+        match (self, &predicate.value) {
+            (Self::String, Value::Str(_))
+            | (Self::Int, Value::Int(_))
+            | (Self::Bool, Value::Bool(_)) => None,
+            (Self::Timestamp, Value::Str(s)) => {
+                if is_timestamp(s) {
+                    None
+                } else {
+                    Some(format!("'{s}' is not a valid timestamp"))
+                }
+            }
+            (Self::Enum(variants), Value::Str(s)) => {
+                // Substring operators can match any value, so only comparisons are checked.
+                let comparison =
+                    matches!(predicate.operator, ClauseOperator::Eq | ClauseOperator::Ne);
+                let eq = Predicate {
+                    operator: ClauseOperator::Eq,
+                    ..predicate.clone()
+                };
+
+                if !comparison
+                    || variants
+                        .iter()
+                        .any(|v| semantics::matches(&eq, Some(&Value::Str(v.clone()))))
+                {
+                    None
+                } else {
+                    Some(format!(
+                        "'{s}' is not a valid value for property '{path}'; expected {self}"
+                    ))
+                }
+            }
+            _ => Some(mismatch()),
+        }
+    }
+}
+
+/// Describe the type as it would appear in a sentence, such as "an integer".
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String => f.write_str("a string"),
+            Self::Int => f.write_str("an integer"),
+            Self::Bool => f.write_str("a boolean"),
+            Self::Timestamp => f.write_str("a timestamp"),
+            Self::Enum(variants) => {
+                f.write_str("one of ")?;
+                for (i, variant) in variants.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "'{variant}'")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Check if `s` is an RFC 3339 date or date-time, or a relative time such as `now-7d`.
+fn is_timestamp(s: &str) -> bool {
+    if let Some(offset) = s.strip_prefix("now") {
+        return offset.is_empty() || is_relative_offset(offset);
+    }
+
+    let (date, time) = match s.find(['T', 't']) {
+        Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+        None => (s, None),
+    };
+
+    is_date(date) && time.is_none_or(is_time)
+}
+
+/// `+` or `-`, then digits, then a unit of `s`, `m`, `h`, `d`, or `w`.
+fn is_relative_offset(offset: &str) -> bool {
+    let Some(rest) = offset.strip_prefix(['+', '-']) else {
+        return false;
+    };
+
+    let Some(digits) = rest.strip_suffix(['s', 'm', 'h', 'd', 'w']) else {
+        return false;
+    };
+
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `YYYY-MM-DD`.
+fn is_date(date: &str) -> bool {
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+
+    match (number(year, 4), number(month, 2), number(day, 2)) {
+        (Some(year), Some(month), Some(day)) => is_valid_date(i64::from(year), month, day),
+        _ => false,
+    }
+}
+
+/// `HH:MM:SS`, optionally with fractional seconds, followed by `Z` or a `±HH:MM` offset.
+fn is_time(time: &str) -> bool {
+    let (time, zone) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, None)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        (&time[..pos], Some(&time[pos + 1..]))
+    } else {
+        return false;
+    };
+
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+
+    let mut parts = time.split(':');
+    let (Some(hour), Some(minute), Some(second), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+
+    number(hour, 2).is_some_and(|h| h < 24)
+        && number(minute, 2).is_some_and(|m| m < 60)
+        // Allow a leap second.
+        && number(second, 2).is_some_and(|s| s <= 60)
+        && fraction.is_none_or(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()))
+        && zone.is_none_or(|zone| {
+            zone.split_once(':').is_some_and(|(h, m)| {
+                number(h, 2).is_some_and(|h| h < 24) && number(m, 2).is_some_and(|m| m < 60)
+            })
+        })
+}
+
+/// Parse `digits` as a number if it is exactly `len` ASCII digits.
+fn number(digits: &str, len: usize) -> Option<u32> {
+    if digits.len() == len && digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::ClauseOperator, parse, Fix};
+
+    use super::{is_timestamp, Deprecation, PropertySchema, Schema, ValueType};
+
+    fn schema() -> Schema {
+        [
+            ("hostname", PropertySchema::new(ValueType::String)),
+            ("online", PropertySchema::new(ValueType::Bool)),
+            ("agent.version", PropertySchema::new(ValueType::Int)),
+            ("last_seen", PropertySchema::new(ValueType::Timestamp)),
+            (
+                "platform_name",
+                PropertySchema::new(ValueType::Enum(vec![
+                    "Windows".into(),
+                    "Mac".into(),
+                    "Linux".into(),
+                ])),
+            ),
+            (
+                "tags",
+                PropertySchema::new(ValueType::String)
+                    .with_operators([ClauseOperator::Eq, ClauseOperator::Contains])
+                    .with_exact(false),
+            ),
+            (
+                "platform_version",
+                PropertySchema::new(ValueType::String)
+                    .with_aliases(["os_version".parse().unwrap()]),
+            ),
+            (
+                "agent_version",
+                PropertySchema::new(ValueType::Int).with_deprecation(Deprecation {
+                    note: Some("it will be removed in 2024".into()),
+                    replacement: Some("agent.version".parse().unwrap()),
+                }),
+            ),
+            (
+                "local_ip",
+                PropertySchema::new(ValueType::String).with_deprecation(Deprecation::default()),
+            ),
+        ]
+        .into_iter()
+        .map(|(path, property)| (path.parse().unwrap(), property))
+        .collect()
+    }
+
+    #[track_caller]
+    fn check(input: &str, expected: &[&str]) {
+        let actual = schema()
+            .validate(&parse(input))
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn valid() {
+        check(
+            "hostname:~'web'+online:true+agent.version:>=7+last_seen:>'2022-04-01'",
+            &[],
+        );
+        check("platform_name:'windows',platform_name:!'Lin*'", &[]);
+        check("tags:~'prod'+hostname:$name", &[]);
+    }
+
+    #[test]
+    fn unknown_property() {
+        check(
+            "hostnmae:'x'",
+            &["At 0..8, unknown property 'hostnmae'; did you mean 'hostname'?"],
+        );
+        check("agent:1", &["At 0..5, unknown property 'agent'"]);
+        check("serial:'x'", &["At 0..6, unknown property 'serial'"]);
+    }
+
+    #[test]
+    fn unknown_property_fix() {
+        let input = "online:true+agent.verison:>5";
+        let diagnostics = schema().validate(&parse(input));
+        let fix = diagnostics[0].fix().unwrap();
+        assert_eq!(fix.title(), "Replace with 'agent.version'");
+        assert_eq!(
+            fix.apply(input).as_deref(),
+            Some("online:true+agent.version:>5")
+        );
+    }
+
+    #[test]
+    fn type_mismatch() {
+        check(
+            "online:'yes'",
+            &["At 7..12, property 'online' expects a boolean, found a string"],
+        );
+        check(
+            "agent.version:'7'",
+            &["At 14..17, property 'agent.version' expects an integer, found a string"],
+        );
+        check(
+            "last_seen:>'yesterday'",
+            &["At 11..22, 'yesterday' is not a valid timestamp"],
+        );
+        check(
+            "platform_name:['windows']",
+            &["At 14..25, 'windows' is not a valid value for property 'platform_name'; expected one of 'Windows', 'Mac', 'Linux'"],
+        );
+    }
+
+    #[test]
+    fn illegal_operator() {
+        check(
+            "platform_name:>'Mac'",
+            &["At 14..15, property 'platform_name' does not support the '>' operator"],
+        );
+        check(
+            "tags:!'x'",
+            &["At 5..6, property 'tags' does not support the '!' operator"],
+        );
+        check(
+            "tags:['x']",
+            &["At 5..10, property 'tags' does not support exact matches"],
+        );
+    }
+
+    #[test]
+    fn aliases_and_deprecations() {
+        check(
+            "os_version:'11'",
+            &["At 0..10, property 'os_version' is an alias of 'platform_version'"],
+        );
+        check(
+            "agent_version:>5+local_ip:'10.0.0.1'",
+            &[
+                "At 0..13, property 'agent_version' is deprecated; it will be removed in 2024; use 'agent.version' instead",
+                "At 17..25, property 'local_ip' is deprecated",
+            ],
+        );
+
+        let input = "os_version:'11'+agent_version:>5";
+        let edits = schema()
+            .validate(&parse(input))
+            .iter()
+            .filter_map(|d| d.fix())
+            .flat_map(|fix| fix.edits().to_vec())
+            .collect();
+        assert_eq!(
+            Fix::new("Apply all", edits).apply(input).as_deref(),
+            Some("platform_version:'11'+agent.version:>5")
+        );
+        assert_eq!(
+            schema().rewrite(&parse(input)),
+            "platform_version:'11'+agent.version:>5"
+        );
+    }
+
+    #[test]
+    fn inside_error_region() {
+        check(
+            "online:true+hostnmae:",
+            &["At 12..20, unknown property 'hostnmae'; did you mean 'hostname'?"],
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        assert!(is_timestamp("2022-04-01"));
+        assert!(is_timestamp("2022-04-01T12:30:00Z"));
+        assert!(is_timestamp("2022-04-01T12:30:00.123+05:30"));
+        assert!(is_timestamp("now"));
+        assert!(is_timestamp("now-7d"));
+        assert!(!is_timestamp("now-d"));
+        assert!(!is_timestamp("2022-13-01"));
+        assert!(!is_timestamp("2022-04-31"));
+        assert!(!is_timestamp("2023-02-29T00:00:00Z"));
+        assert!(is_timestamp("2024-02-29T00:00:00Z"));
+        assert!(!is_timestamp("2022-04-01T12:30:00"));
+        assert!(!is_timestamp("2022-04-01T25:00:00Z"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Deserialization of [`Schema`] from the documented file format.
+//!
+//! The format is described by `fql-schema.json` in the root of the crate. Files are
+//! read into the plain structs below first, so that errors about the shape of the
+//! file come from `serde`, and errors about its meaning come from [`SchemaFile::into_schema`].
+
+use std::collections::BTreeMap;
+
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{ast::ClauseOperator, PropertyPath};
+
+use super::{Deprecation, PropertySchema, Schema, ValueType};
+
+impl<'de> Deserialize<'de> for Schema {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SchemaFile::deserialize(deserializer)?
+            .into_schema()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    /// Editors use this to find the JSON Schema for the file; it is otherwise ignored.
+    #[serde(rename = "$schema", default)]
+    _schema: Option<String>,
+    properties: BTreeMap<PropertyPath, PropertyFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PropertyFile {
+    #[serde(rename = "type")]
+    value_type: TypeName,
+    values: Option<Vec<String>>,
+    operators: Option<Vec<ClauseOperator>>,
+    exact: Option<bool>,
+    description: Option<String>,
+    deprecated: Option<DeprecatedFile>,
+    #[serde(default)]
+    aliases: Vec<PropertyPath>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TypeName {
+    String,
+    Integer,
+    Boolean,
+    Timestamp,
+    Enum,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeprecatedFile {
+    Flag(bool),
+    Details(DeprecationFile),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeprecationFile {
+    note: Option<String>,
+    replacement: Option<PropertyPath>,
+}
+
+impl SchemaFile {
+    fn into_schema(self) -> Result<Schema, String> {
+        let mut schema = Schema::new();
+        for (path, file) in self.properties {
+            for alias in &file.aliases {
+                if let Some((canonical, _)) = schema.resolve(alias) {
+                    return Err(format!(
+                        "alias '{alias}' of property '{path}' is already used by property '{canonical}'"
+                    ));
+                }
+            }
+
+            let property = file.into_property(&path)?;
+            schema.insert(path, property);
+        }
+
+        // Aliases are only checked against earlier properties above, so check them
+        // against every property once they are all known.
+        for (alias, canonical) in &schema.aliases {
+            if schema.properties.contains_key(alias) {
+                return Err(format!(
+                    "alias '{alias}' of property '{canonical}' is also declared as a property"
+                ));
+            }
+        }
+
+        for (path, property) in &schema.properties {
+            let replacement = property
+                .deprecation()
+                .and_then(|deprecation| deprecation.replacement.as_ref());
+            if let Some(replacement) = replacement {
+                if schema.resolve(replacement).is_none() {
+                    return Err(format!(
+                        "replacement '{replacement}' of property '{path}' is not declared"
+                    ));
+                }
+            }
+        }
+
+        Ok(schema)
+    }
+}
+
+impl PropertyFile {
+    fn into_property(self, path: &PropertyPath) -> Result<PropertySchema, String> {
+        let value_type = match (self.value_type, self.values) {
+            (TypeName::Enum, Some(values)) if !values.is_empty() => ValueType::Enum(values),
+            (TypeName::Enum, _) => {
+                return Err(format!("enum property '{path}' must list its values"));
+            }
+            (_, Some(_)) => {
+                return Err(format!(
+                    "property '{path}' has values, but only enum properties can list values"
+                ));
+            }
+            (TypeName::String, None) => ValueType::String,
+            (TypeName::Integer, None) => ValueType::Int,
+            (TypeName::Boolean, None) => ValueType::Bool,
+            (TypeName::Timestamp, None) => ValueType::Timestamp,
+        };
+
+        let mut property = PropertySchema::new(value_type).with_aliases(self.aliases);
+        if let Some(operators) = self.operators {
+            property = property.with_operators(operators);
+        }
+
+        if let Some(exact) = self.exact {
+            property = property.with_exact(exact);
+        }
+
+        if let Some(description) = self.description {
+            property = property.with_description(description);
+        }
+
+        match self.deprecated {
+            None | Some(DeprecatedFile::Flag(false)) => {}
+            Some(DeprecatedFile::Flag(true)) => {
+                property = property.with_deprecation(Deprecation::default());
+            }
+            Some(DeprecatedFile::Details(DeprecationFile { note, replacement })) => {
+                property = property.with_deprecation(Deprecation { note, replacement });
+            }
+        }
+
+        Ok(property)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::ClauseOperator, schema::ValueType, PropertyPath};
+
+    use super::Schema;
+
+    #[track_caller]
+    fn path(input: &str) -> PropertyPath {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn from_json() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "$schema": "./fql-schema.json",
+                "properties": {
+                    "hostname": {
+                        "type": "string",
+                        "description": "The name of the device.",
+                        "aliases": ["host_name"]
+                    },
+                    "platform_name": {
+                        "type": "enum",
+                        "values": ["Windows", "Mac", "Linux"],
+                        "operators": ["eq"]
+                    },
+                    "agent_version": {
+                        "type": "integer",
+                        "deprecated": { "note": "Removed in v2", "replacement": "agent.version" }
+                    },
+                    "agent.version": { "type": "string" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let (canonical, hostname) = schema.resolve(&path("host_name")).unwrap();
+        assert_eq!(canonical, &path("hostname"));
+        assert_eq!(hostname.description(), Some("The name of the device."));
+
+        let platform = schema.get(&path("platform_name")).unwrap();
+        assert!(matches!(platform.value_type(), ValueType::Enum(v) if v.len() == 3));
+        assert_eq!(platform.operators(), [ClauseOperator::Eq]);
+
+        let deprecation = schema
+            .get(&path("agent_version"))
+            .unwrap()
+            .deprecation()
+            .unwrap();
+        assert_eq!(deprecation.replacement, Some(path("agent.version")));
+    }
+
+    #[track_caller]
+    fn error(json: &str) -> String {
+        serde_json::from_str::<Schema>(json)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(error(r#"{ "properties": { "a": { "type": "enum" } } }"#)
+            .starts_with("enum property 'a' must list its values"));
+        assert!(error(r#"{ "properties": { "A": { "type": "string" } } }"#)
+            .contains("invalid segment 'A'"));
+        assert!(
+            error(r#"{ "properties": { "a": { "type": "string", "color": "red" } } }"#)
+                .contains("unknown field `color`")
+        );
+        assert!(error(
+            r#"{ "properties": {
+                "a": { "type": "string", "aliases": ["b"] },
+                "b": { "type": "string" }
+            } }"#
+        )
+        .starts_with("alias 'b' of property 'a' is also declared as a property"));
+    }
+
+    #[test]
+    fn invalid_deprecation() {
+        // A misspelled field would otherwise be read as a deprecation without details.
+        assert!(error(
+            r#"{ "properties": {
+                "a": { "type": "string", "deprecated": { "replacment": "b" } },
+                "b": { "type": "string" }
+            } }"#
+        )
+        .contains("did not match any variant"));
+        assert!(error(
+            r#"{ "properties": {
+                "a": { "type": "string", "deprecated": { "replacement": "c" } },
+                "b": { "type": "string", "aliases": ["bb"] }
+            } }"#
+        )
+        .starts_with("replacement 'c' of property 'a' is not declared"));
+
+        // A replacement may be an alias of another property.
+        let schema: Schema = serde_json::from_str(
+            r#"{ "properties": {
+                "a": { "type": "string", "deprecated": { "replacement": "bb" } },
+                "b": { "type": "string", "aliases": ["bb"] }
+            } }"#,
+        )
+        .unwrap();
+        assert!(schema.get(&path("a")).unwrap().deprecation().is_some());
+    }
+
+    /// The JSON Schema shipped with the crate must describe the same types and operators
+    /// as the deserializer accepts.
+    #[test]
+    fn json_schema_matches() {
+        let json_schema: serde_json::Value =
+            serde_json::from_str(include_str!("../../fql-schema.json")).unwrap();
+        let property = &json_schema["$defs"]["property"]["properties"];
+
+        assert_eq!(
+            property["type"]["enum"],
+            serde_json::json!(["string", "integer", "boolean", "timestamp", "enum"])
+        );
+        assert_eq!(
+            property["operators"]["items"]["enum"],
+            serde_json::json!([
+                "eq",
+                "ne",
+                "gt",
+                "ge",
+                "lt",
+                "le",
+                "contains",
+                "not_contains"
+            ])
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+
+use rowan::ast::AstNode;
+
+use crate::{ast::Property, Fix, Parse, PropertyPath, Spanned, TextEdit};
+
+use super::Schema;
+
+impl Schema {
+    /// Replace aliases and deprecated properties in `parse` with the properties that
+    /// should be used instead, leaving all other text unchanged.
+    ///
+    /// Aliases are replaced by their canonical path, and deprecated properties by their
+    /// replacement, if the deprecation names one. Unknown properties are left as they are.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::schema::{Deprecation, PropertySchema, Schema, ValueType};
+    ///
+    /// let mut schema = Schema::new();
+    /// schema.insert(
+    ///     "platform_version".parse().unwrap(),
+    ///     PropertySchema::new(ValueType::String).with_aliases(["os_version".parse().unwrap()]),
+    /// );
+    /// schema.insert(
+    ///     "agent_version".parse().unwrap(),
+    ///     PropertySchema::new(ValueType::String).with_deprecation(Deprecation {
+    ///         note: None,
+    ///         replacement: Some("agent.version".parse().unwrap()),
+    ///     }),
+    /// );
+    ///
+    /// let parse = fql::parse("(os_version:'11',agent_version:>'7')+unknown:1");
+    /// assert_eq!(
+    ///     schema.rewrite(&parse),
+    ///     "(platform_version:'11',agent.version:>'7')+unknown:1"
+    /// );
+    /// ```
+    pub fn rewrite(&self, parse: &Parse) -> String {
+        let root = parse.syntax();
+        let edits = root
+            .descendants()
+            .filter_map(Property::cast)
+            .filter_map(|property| {
+                let preferred = self.preferred(&PropertyPath::from(&property))?;
+                Some(TextEdit {
+                    range: property.span(),
+                    replacement: preferred.to_string(),
+                })
+            })
+            .collect();
+
+        Fix::new("Replace aliases and deprecated properties", edits)
+            .apply(&root.to_string())
+            .expect("properties are disjoint ranges of the tree")
+    }
+
+    /// The path which should be written instead of `path`, or `None` if `path` is
+    /// unknown or is already the preferred way to refer to its property.
+    pub(super) fn preferred(&self, path: &PropertyPath) -> Option<PropertyPath> {
+        let mut current = path.clone();
+        let mut seen = BTreeSet::new();
+
+        // Follow aliases and replacements until reaching a property which is neither,
+        // stopping if the schema's replacements form a cycle.
+        while seen.insert(current.clone()) {
+            let Some((canonical, property)) = self.resolve(&current) else {
+                break;
+            };
+
+            match property.deprecation().and_then(|d| d.replacement.as_ref()) {
+                Some(replacement) => current = replacement.clone(),
+                None => {
+                    current = canonical.clone();
+                    break;
+                }
+            }
+        }
+
+        (current != *path).then_some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse,
+        schema::{Deprecation, PropertySchema, Schema, ValueType},
+        PropertyPath,
+    };
+
+    #[track_caller]
+    fn path(input: &str) -> PropertyPath {
+        input.parse().unwrap()
+    }
+
+    fn deprecated(replacement: &str) -> PropertySchema {
+        PropertySchema::new(ValueType::String).with_deprecation(Deprecation {
+            note: None,
+            replacement: Some(path(replacement)),
+        })
+    }
+
+    #[test]
+    fn follows_chains() {
+        let mut schema = Schema::new();
+        schema.insert(path("a"), deprecated("b"));
+        schema.insert(
+            path("c"),
+            PropertySchema::new(ValueType::String).with_aliases([path("b")]),
+        );
+
+        assert_eq!(schema.preferred(&path("a")), Some(path("c")));
+        assert_eq!(schema.preferred(&path("b")), Some(path("c")));
+        assert_eq!(schema.preferred(&path("c")), None);
+        assert_eq!(schema.preferred(&path("unknown")), None);
+    }
+
+    #[test]
+    fn stops_on_cycles() {
+        let mut schema = Schema::new();
+        schema.insert(path("a"), deprecated("b"));
+        schema.insert(path("b"), deprecated("a"));
+
+        assert_eq!(schema.rewrite(&parse("a:'x'+b:'y'")), "a:'x'+b:'y'");
+    }
+
+    #[test]
+    fn preserves_incomplete_input() {
+        let mut schema = Schema::new();
+        schema.insert(path("new_name"), PropertySchema::new(ValueType::String));
+        schema.insert(path("old_name"), deprecated("new_name"));
+
+        assert_eq!(
+            schema.rewrite(&parse("old_name:'a'+(old_name:")),
+            "new_name:'a'+(new_name:"
+        );
+    }
+}
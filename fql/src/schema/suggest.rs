@@ -0,0 +1,127 @@
+use crate::PropertyPath;
+
+use super::Schema;
+
+impl Schema {
+    /// Find the property which `path` was most likely meant to be, for "did you mean"
+    /// suggestions.
+    ///
+    /// Paths are compared segment by segment: segments which match exactly cost nothing,
+    /// misspelled segments cost their edit distance, and missing or extra segments cost
+    /// their length. The allowed distance is proportional to the part of `path` after
+    /// any segments it shares with the candidate, so a long shared prefix such as
+    /// `device_policies.` doesn't make unrelated suggestions acceptable. Deprecated
+    /// properties are never suggested.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fql::schema::{PropertySchema, Schema, ValueType};
+    ///
+    /// let schema = ["hostname", "device_policies.prevention.policy_id"]
+    ///     .into_iter()
+    ///     .map(|p| (p.parse().unwrap(), PropertySchema::new(ValueType::String)))
+    ///     .collect::<Schema>();
+    ///
+    /// let suggest = |p: &str| schema.suggest(&p.parse().unwrap()).map(|p| p.to_string());
+    /// assert_eq!(suggest("hostnme").as_deref(), Some("hostname"));
+    /// assert_eq!(
+    ///     suggest("device_policies.prevnetion.policy_id").as_deref(),
+    ///     Some("device_policies.prevention.policy_id")
+    /// );
+    /// assert_eq!(suggest("device_policies.sensor_update.policy_id"), None);
+    /// ```
+    pub fn suggest(&self, path: &PropertyPath) -> Option<&PropertyPath> {
+        self.properties()
+            .filter(|(_, property)| property.deprecation().is_none())
+            .filter_map(|(candidate, _)| {
+                let distance = segment_distance(path.segments(), candidate.segments());
+                let shared = path
+                    .segments()
+                    .iter()
+                    .zip(candidate.segments())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let rest = path.segments()[shared..]
+                    .iter()
+                    .map(|s| s.len() + 1)
+                    .sum::<usize>();
+
+                (distance > 0 && distance <= (rest / 3).max(1)).then_some((distance, candidate))
+            })
+            // `min_by_key` keeps the first of equally-close candidates, which is the
+            // first in path order.
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// Edit distance between two paths, where each segment is inserted, deleted, or
+/// substituted as a unit.
+fn segment_distance(a: &[String], b: &[String]) -> usize {
+    // Each segment is counted with its period when inserted or deleted.
+    let weight = |s: &String| s.chars().count() + 1;
+
+    let mut previous = Vec::with_capacity(b.len() + 1);
+    previous.push(0);
+    for segment in b {
+        previous.push(previous.last().unwrap() + weight(segment));
+    }
+
+    for a_segment in a {
+        let mut current = Vec::with_capacity(b.len() + 1);
+        current.push(previous[0] + weight(a_segment));
+        for (j, b_segment) in b.iter().enumerate() {
+            let cost = (previous[j] + levenshtein(a_segment, b_segment))
+                .min(previous[j + 1] + weight(a_segment))
+                .min(current[j] + weight(b_segment));
+            current.push(cost);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Character-level edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = Vec::with_capacity(b.len() + 1);
+        current.push(i + 1);
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PropertyPath;
+
+    use super::{levenshtein, segment_distance};
+
+    #[track_caller]
+    fn distance(a: &str, b: &str) -> usize {
+        let a = a.parse::<PropertyPath>().unwrap();
+        let b = b.parse::<PropertyPath>().unwrap();
+        segment_distance(a.segments(), b.segments())
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(levenshtein("hostnme", "hostname"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(distance("hostnme", "hostname"), 1);
+        assert_eq!(distance("device.os", "device.os"), 0);
+        assert_eq!(distance("os_version", "device.os_version"), 7);
+        assert_eq!(distance("a.bc.d", "a.bd.d"), 1);
+    }
+}
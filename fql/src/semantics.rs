@@ -0,0 +1,847 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    ast::ClauseOperator,
+    filter::{Filter, Predicate, Value},
+};
+
+/// How much the semantic checks may assume about the meaning of clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Semantics {
+    /// Clauses are opaque: two clauses are only related if they are identical.
+    #[default]
+    Propositional,
+    /// Clauses on the same property are related by what their operators mean, so
+    /// `a:>5` implies `a:>3` and excludes `a:2`.
+    ///
+    /// This assumes every property holds at most one value per record. When it cannot
+    /// decide whether a combination of string clauses is possible, it assumes it is.
+    Operators,
+}
+
+/// The most work a single check may do before giving up, counted in formula nodes
+/// simplified and predicates tested. This bounds the time spent on hostile filters,
+/// for which deciding equivalence or implication can take exponential time.
+const WORK_BUDGET: usize = 1_000_000;
+
+/// The result of comparing two filters with [`equivalent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// The filters match the same records.
+    Equivalent,
+    /// The filters disagree on at least one record, described by the counterexample.
+    Different(Counterexample),
+    /// The filters are too complex to compare within the work budget.
+    Undecided,
+}
+
+impl Equivalence {
+    pub fn is_equivalent(&self) -> bool {
+        matches!(self, Self::Equivalent)
+    }
+}
+
+/// An assignment of truth values to clauses under which two filters disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    /// The clauses which decide the outcome, and whether each one matches.
+    ///
+    /// Clauses which are not listed do not affect the outcome.
+    pub assignment: Vec<(Predicate, bool)>,
+    /// Whether the left filter matches under this assignment.
+    pub left: bool,
+    /// Whether the right filter matches under this assignment.
+    pub right: bool,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (predicate, value)) in self.assignment.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{predicate} is {value}")?;
+        }
+
+        write!(f, " => left is {}, right is {}", self.left, self.right)
+    }
+}
+
+/// Decide whether two filters match the same records, treating each clause as
+/// an opaque condition.
+///
+/// # Example
+/// ```rust
+/// use fql::semantics::equivalent;
+///
+/// let a = fql::parse("a:1+(b:2,c:3)").to_filter().unwrap();
+/// let b = fql::parse("(a:1+b:2),(a:1+c:3)").to_filter().unwrap();
+/// assert!(equivalent(&a, &b).is_equivalent());
+/// ```
+pub fn equivalent(left: &Filter, right: &Filter) -> Equivalence {
+    equivalent_with(left, right, Semantics::Propositional)
+}
+
+/// Decide whether two filters match the same records under `semantics`.
+///
+/// Deciding this can take time exponential in the number of clauses, so the check
+/// gives up and returns [`Equivalence::Undecided`] after a fixed amount of work.
+pub fn equivalent_with(left: &Filter, right: &Filter, semantics: Semantics) -> Equivalence {
+    if left.clone().normalize() == right.clone().normalize() {
+        return Equivalence::Equivalent;
+    }
+
+    let mut atoms = Atoms::default();
+    let lhs = atoms.formula(left);
+    let rhs = atoms.formula(right);
+
+    // The filters differ exactly when `lhs XOR rhs` is satisfiable.
+    let differ = Formula::Or(vec![
+        Formula::And(vec![lhs.clone(), Formula::Not(Box::new(rhs.clone()))]),
+        Formula::And(vec![Formula::Not(Box::new(lhs.clone())), rhs.clone()]),
+    ]);
+
+    match Solver::new(&atoms.predicates, semantics).solve(&differ) {
+        Ok(Some(assignment)) => Equivalence::Different(Counterexample {
+            left: lhs.eval(&assignment),
+            right: rhs.eval(&assignment),
+            assignment: atoms.describe(&assignment),
+        }),
+        Ok(None) => Equivalence::Equivalent,
+        Err(OutOfBudget) => Equivalence::Undecided,
+    }
+}
+
+/// The result of checking an implication with [`implication`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Implication {
+    /// Every record matched by the premise is matched by the conclusion.
+    Implied,
+    /// Some record matched by the premise is not matched by the conclusion, or the
+    /// check can't rule one out.
+    NotImplied,
+    /// The filters are too complex to check within the work budget.
+    Undecided,
+}
+
+/// Check whether every record matched by `premise` is also matched by `conclusion`.
+///
+/// This is [`implication`], treating an undecided check as failing.
+///
+/// # Example
+/// ```rust
+/// use fql::semantics::implies;
+///
+/// let request = fql::parse("cid:['abc']+last_seen:>100").to_filter().unwrap();
+/// let policy = fql::parse("cid:['abc']+last_seen:>=50").to_filter().unwrap();
+/// assert!(implies(&request, &policy));
+/// assert!(!implies(&policy, &request));
+/// ```
+pub fn implies(premise: &Filter, conclusion: &Filter) -> bool {
+    implication(premise, conclusion) == Implication::Implied
+}
+
+/// Check whether every record matched by `premise` is also matched by `conclusion`.
+///
+/// This uses [`Semantics::Operators`], so numeric ranges, exact and contains string
+/// matches, and negations on the same property are taken into account. The check is
+/// conservative: when it cannot prove the implication, it returns
+/// [`Implication::NotImplied`].
+///
+/// Deciding this can take time exponential in the number of clauses, so the check
+/// gives up and returns [`Implication::Undecided`] after a fixed amount of work.
+pub fn implication(premise: &Filter, conclusion: &Filter) -> Implication {
+    let mut atoms = Atoms::default();
+    let premise = atoms.formula(premise);
+    let conclusion = atoms.formula(conclusion);
+
+    // The implication fails exactly when some record matches the premise but not the conclusion.
+    let escape = Formula::And(vec![premise, Formula::Not(Box::new(conclusion))]);
+    match Solver::new(&atoms.predicates, Semantics::Operators).solve(&escape) {
+        Ok(None) => Implication::Implied,
+        Ok(Some(_)) => Implication::NotImplied,
+        Err(OutOfBudget) => Implication::Undecided,
+    }
+}
+
+/// A boolean formula over clause atoms, used internally by the solver.
+#[derive(Debug, Clone)]
+enum Formula {
+    Const(bool),
+    Atom(usize),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    /// Substitute the assigned atoms and fold constants, adding the number of nodes
+    /// visited to `work`.
+    fn simplify(&self, assignment: &[Option<bool>], work: &mut usize) -> Formula {
+        *work += 1;
+        match self {
+            Self::Const(_) => self.clone(),
+            Self::Atom(atom) => match assignment[*atom] {
+                Some(value) => Self::Const(value),
+                None => self.clone(),
+            },
+            Self::Not(inner) => match inner.simplify(assignment, work) {
+                Self::Const(value) => Self::Const(!value),
+                other => Self::Not(Box::new(other)),
+            },
+            Self::And(children) => Self::simplify_children(children, assignment, work, true),
+            Self::Or(children) => Self::simplify_children(children, assignment, work, false),
+        }
+    }
+
+    /// Simplify the children of an `And` (if `is_and`) or an `Or`.
+    ///
+    /// A child equal to `!is_and` decides the whole node, and children equal to
+    /// `is_and` can be dropped.
+    fn simplify_children(
+        children: &[Formula],
+        assignment: &[Option<bool>],
+        work: &mut usize,
+        is_and: bool,
+    ) -> Self {
+        let mut remaining = Vec::with_capacity(children.len());
+        for child in children {
+            match child.simplify(assignment, work) {
+                Self::Const(value) if value == is_and => {}
+                Self::Const(value) => return Self::Const(value),
+                other => remaining.push(other),
+            }
+        }
+
+        match remaining.len() {
+            0 => Self::Const(is_and),
+            1 => remaining.pop().unwrap(),
+            _ if is_and => Self::And(remaining),
+            _ => Self::Or(remaining),
+        }
+    }
+
+    /// Evaluate the formula, treating unassigned atoms as not matching.
+    fn eval(&self, assignment: &[Option<bool>]) -> bool {
+        match self {
+            Self::Const(value) => *value,
+            Self::Atom(atom) => assignment[*atom].unwrap_or(false),
+            Self::Not(inner) => !inner.eval(assignment),
+            Self::And(children) => children.iter().all(|c| c.eval(assignment)),
+            Self::Or(children) => children.iter().any(|c| c.eval(assignment)),
+        }
+    }
+
+    fn first_atom(&self) -> Option<usize> {
+        match self {
+            Self::Const(_) => None,
+            Self::Atom(atom) => Some(*atom),
+            Self::Not(inner) => inner.first_atom(),
+            Self::And(children) | Self::Or(children) => children.iter().find_map(Self::first_atom),
+        }
+    }
+}
+
+/// Interns the distinct predicates of the filters being compared.
+#[derive(Default)]
+struct Atoms {
+    predicates: Vec<Predicate>,
+    indices: BTreeMap<Predicate, usize>,
+}
+
+impl Atoms {
+    fn formula(&mut self, filter: &Filter) -> Formula {
+        match filter {
+            Filter::And(children) => {
+                Formula::And(children.iter().map(|c| self.formula(c)).collect())
+            }
+            Filter::Or(children) => Formula::Or(children.iter().map(|c| self.formula(c)).collect()),
+            Filter::Clause(predicate) => Formula::Atom(self.intern(predicate)),
+            Filter::True => Formula::Const(true),
+            Filter::False => Formula::Const(false),
+        }
+    }
+
+    fn intern(&mut self, predicate: &Predicate) -> usize {
+        if let Some(index) = self.indices.get(predicate) {
+            return *index;
+        }
+
+        let index = self.predicates.len();
+        self.predicates.push(predicate.clone());
+        self.indices.insert(predicate.clone(), index);
+        index
+    }
+
+    fn describe(&self, assignment: &[Option<bool>]) -> Vec<(Predicate, bool)> {
+        self.predicates
+            .iter()
+            .zip(assignment)
+            .filter_map(|(predicate, value)| Some((predicate.clone(), (*value)?)))
+            .collect()
+    }
+}
+
+/// The search gave up because it used up its [`WORK_BUDGET`].
+struct OutOfBudget;
+
+/// A backtracking search for an assignment of atoms which satisfies a formula.
+///
+/// The search keeps its own stack of decisions rather than recursing, since a
+/// filter can have more clauses than the call stack has room for frames.
+struct Solver<'a> {
+    predicates: &'a [Predicate],
+    semantics: Semantics,
+    assignment: Vec<Option<bool>>,
+    work: usize,
+}
+
+/// An atom the search has assigned, and the formula as it was before.
+struct Decision {
+    atom: usize,
+    formula: Formula,
+    /// The values not yet tried for the atom.
+    untried: std::array::IntoIter<bool, 2>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(predicates: &'a [Predicate], semantics: Semantics) -> Self {
+        Self {
+            predicates,
+            semantics,
+            assignment: vec![None; predicates.len()],
+            work: 0,
+        }
+    }
+
+    /// Find an assignment which satisfies `formula`, or `None` if there is none.
+    fn solve(mut self, formula: &Formula) -> Result<Option<Vec<Option<bool>>>, OutOfBudget> {
+        let mut decisions: Vec<Decision> = vec![];
+        let mut next = Some(self.simplify(formula)?);
+
+        loop {
+            match next.take() {
+                Some(Formula::Const(true)) => return Ok(Some(self.assignment)),
+                Some(Formula::Const(false)) | None => {}
+                Some(formula) => decisions.push(Decision {
+                    atom: formula
+                        .first_atom()
+                        .expect("non-constant formula has an atom"),
+                    formula,
+                    untried: [true, false].into_iter(),
+                }),
+            }
+
+            let Some(decision) = decisions.last_mut() else {
+                return Ok(None);
+            };
+
+            let atom = decision.atom;
+            match decision.untried.next() {
+                Some(value) => {
+                    self.assignment[atom] = Some(value);
+                    if self.consistent(atom)? {
+                        let formula = decision.formula.clone();
+                        next = Some(self.simplify(&formula)?);
+                    }
+                }
+                None => {
+                    self.assignment[atom] = None;
+                    decisions.pop();
+                }
+            }
+        }
+    }
+
+    fn simplify(&mut self, formula: &Formula) -> Result<Formula, OutOfBudget> {
+        let formula = formula.simplify(&self.assignment, &mut self.work);
+        self.charge(0)?;
+        Ok(formula)
+    }
+
+    /// Add `work` to the work done, failing if that exceeds the budget.
+    fn charge(&mut self, work: usize) -> Result<(), OutOfBudget> {
+        self.work = self.work.saturating_add(work);
+        if self.work > WORK_BUDGET {
+            Err(OutOfBudget)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check that the atoms assigned so far on the same property as `atom` can
+    /// hold together.
+    fn consistent(&mut self, atom: usize) -> Result<bool, OutOfBudget> {
+        if self.semantics == Semantics::Propositional {
+            return Ok(true);
+        }
+
+        let property = &self.predicates[atom].property;
+        let constraints = self
+            .predicates
+            .iter()
+            .zip(&self.assignment)
+            .filter(|(predicate, _)| predicate.property == *property)
+            .filter_map(|(predicate, value)| Some((predicate, (*value)?)))
+            .collect::<Vec<_>>();
+
+        // `satisfiable` tests each constraint against a few candidate values for
+        // each constraint.
+        let n = constraints.len();
+        self.charge(self.predicates.len() + n.saturating_mul(n).saturating_mul(8))?;
+        Ok(satisfiable(&constraints))
+    }
+}
+
+/// Check whether a single property value could give every predicate its
+/// required truth value.
+///
+/// The search tries a set of candidate values derived from the predicates. The
+/// candidates are complete for missing, boolean, and integer values; if no
+/// candidate works and a string value is not ruled out, the constraints are
+/// assumed to be satisfiable unless [`strings_contradict`] finds a conflict.
+fn satisfiable(constraints: &[(&Predicate, bool)]) -> bool {
+    // `!` and `!~` match exactly the records their positive forms reject, so a
+    // clause and its negation can never have the same truth value.
+    for (i, (left, left_value)) in constraints.iter().enumerate() {
+        let contradicted = constraints[i + 1..].iter().any(|(right, right_value)| {
+            left_value == right_value
+                && left.operator.is_negated() != right.operator.is_negated()
+                && left.operator.negate() == right.operator
+                && left.value == right.value
+                && left.exact == right.exact
+        });
+
+        if contradicted {
+            return false;
+        }
+    }
+
+    let candidates = candidates(constraints);
+    let test = |value: Option<&Value>| {
+        constraints
+            .iter()
+            .all(|(predicate, expected)| matches(predicate, value) == *expected)
+    };
+
+    if test(None) || candidates.iter().any(|value| test(Some(value))) {
+        return true;
+    }
+
+    // A value is required to be a string if some constraint needs a present value
+    // of the predicate's type. If anything pins the value down further, the
+    // candidates above were exhaustive.
+    let mut string_possible = true;
+    for (predicate, expected) in constraints {
+        let requires_present = *expected != predicate.operator.is_negated();
+        if !requires_present {
+            continue;
+        }
+
+        match &predicate.value {
+            Value::Str(_) if predicate.exact && predicate.operator == ClauseOperator::Eq => {
+                string_possible = false;
+            }
+            Value::Str(_) if predicate.exact && predicate.operator == ClauseOperator::Ne => {
+                string_possible = false;
+            }
+            Value::Str(_) => {}
+            Value::Int(_) | Value::Bool(_) => string_possible = false,
+        }
+    }
+
+    string_possible && !strings_contradict(constraints)
+}
+
+/// Look for a conflict between constraints which all apply to a present string value.
+///
+/// This only recognizes conflicts between case-insensitive matches, between
+/// required and forbidden substrings, and between string range bounds; finding
+/// none does not prove the constraints can hold.
+fn strings_contradict(constraints: &[(&Predicate, bool)]) -> bool {
+    // A required non-exact match without wildcards fixes the value up to case, and
+    // that is all other case-insensitive clauses can observe.
+    let pinned = constraints
+        .iter()
+        .find_map(|(predicate, expected)| match &predicate.value {
+            Value::Str(value)
+                if !predicate.exact
+                    && !value.contains('*')
+                    && predicate.operator.is_negated() != *expected
+                    && matches!(predicate.operator, ClauseOperator::Eq | ClauseOperator::Ne) =>
+            {
+                Some(Value::Str(value.to_lowercase()))
+            }
+            _ => None,
+        });
+
+    if let Some(pinned) = pinned {
+        let conflict = constraints.iter().any(|(predicate, expected)| {
+            let case_insensitive = !predicate.exact
+                && matches!(predicate.value, Value::Str(_))
+                && !predicate.operator.is_range();
+            case_insensitive && matches(predicate, Some(&pinned)) != *expected
+        });
+
+        if conflict {
+            return true;
+        }
+    }
+
+    // Rewrite `!` and `!~` as their positive forms with the opposite truth value.
+    let constraints = constraints.iter().filter_map(|(predicate, expected)| {
+        let Value::Str(value) = &predicate.value else {
+            return None;
+        };
+
+        let (operator, expected) = if predicate.operator.is_negated() {
+            (predicate.operator.negate(), !expected)
+        } else {
+            (predicate.operator, *expected)
+        };
+
+        Some((operator, value.as_str(), predicate.exact, expected))
+    });
+
+    let mut required = Vec::new();
+    let mut forbidden = Vec::new();
+    let mut lower: Option<(&str, bool)> = None;
+    let mut upper: Option<(&str, bool)> = None;
+
+    for (operator, value, exact, expected) in constraints {
+        match (operator, expected) {
+            (ClauseOperator::Contains, true) => required.push((value.to_string(), exact)),
+            (ClauseOperator::Contains, false) => forbidden.push((value, exact)),
+            (ClauseOperator::Eq, true) if !exact => required.extend(
+                value
+                    .split('*')
+                    .filter(|part| !part.is_empty())
+                    .map(|part| (part.to_string(), false)),
+            ),
+            (ClauseOperator::Eq, false) if !exact && value.chars().all(|c| c == '*') => {
+                return true;
+            }
+            (ClauseOperator::Gt, true) | (ClauseOperator::Le, false) => {
+                lower = tighter(lower, (value, true), |a, b| a > b);
+            }
+            (ClauseOperator::Ge, true) | (ClauseOperator::Lt, false) => {
+                lower = tighter(lower, (value, false), |a, b| a > b);
+            }
+            (ClauseOperator::Lt, true) | (ClauseOperator::Ge, false) => {
+                upper = tighter(upper, (value, true), |a, b| a < b);
+            }
+            (ClauseOperator::Le, true) | (ClauseOperator::Gt, false) => {
+                upper = tighter(upper, (value, false), |a, b| a < b);
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some((lo, lo_strict)), Some((hi, hi_strict))) = (lower, upper) {
+        if lo > hi || (lo == hi && (lo_strict || hi_strict)) {
+            return true;
+        }
+    }
+
+    forbidden.iter().any(|(substring, forbidden_exact)| {
+        required.iter().any(|(required, required_exact)| {
+            if *forbidden_exact {
+                *required_exact && required.contains(substring)
+            } else {
+                required.to_lowercase().contains(&substring.to_lowercase())
+            }
+        })
+    })
+}
+
+/// Pick the tighter of two bounds, where `beyond(a, b)` means `a` is tighter
+/// than `b`. At equal values, a strict bound is tighter.
+fn tighter<'a>(
+    current: Option<(&'a str, bool)>,
+    candidate: (&'a str, bool),
+    beyond: impl Fn(&str, &str) -> bool,
+) -> Option<(&'a str, bool)> {
+    match current {
+        Some(current)
+            if beyond(current.0, candidate.0)
+                || (current.0 == candidate.0 && current.1 >= candidate.1) =>
+        {
+            Some(current)
+        }
+        _ => Some(candidate),
+    }
+}
+
+fn candidates(constraints: &[(&Predicate, bool)]) -> Vec<Value> {
+    let mut values = vec![
+        Value::Bool(true),
+        Value::Bool(false),
+        Value::Int(0),
+        Value::Int(u64::MAX),
+    ];
+    let mut required = String::new();
+    for (predicate, expected) in constraints {
+        match &predicate.value {
+            Value::Int(i) => {
+                values.extend([i.saturating_sub(1), *i, i.saturating_add(1)].map(Value::Int));
+            }
+            Value::Str(s) => {
+                let literal = s.replace('*', "");
+                if *expected != predicate.operator.is_negated() {
+                    required.push_str(&literal);
+                }
+
+                values.extend(
+                    [
+                        s.clone(),
+                        s.to_lowercase(),
+                        s.to_uppercase(),
+                        literal,
+                        format!("{s}\u{0}"),
+                    ]
+                    .map(Value::Str),
+                );
+            }
+            Value::Bool(_) => {}
+        }
+    }
+
+    values.extend([String::new(), "\u{10ffff}".into(), required].map(Value::Str));
+    values
+}
+
+/// Test whether a predicate matches a record where its property has `value`,
+/// or is missing if `value` is `None`.
+///
+/// Non-exact string equality is case-insensitive and treats `*` as a wildcard;
+/// exact equality compares strings verbatim. Contains is case-insensitive unless
+/// exact. Range comparisons apply to integers and, lexicographically, to strings.
+/// Comparing values of different types never matches.
+pub(crate) fn matches(predicate: &Predicate, value: Option<&Value>) -> bool {
+    let positive = match predicate.operator {
+        ClauseOperator::Eq | ClauseOperator::Ne => ClauseOperator::Eq,
+        ClauseOperator::Contains | ClauseOperator::NotContains => ClauseOperator::Contains,
+        other => other,
+    };
+
+    let result = value.is_some_and(|value| match (value, &predicate.value) {
+        (Value::Str(actual), Value::Str(expected)) => match positive {
+            ClauseOperator::Eq if predicate.exact => actual == expected,
+            ClauseOperator::Eq => wildcard_match(&expected.to_lowercase(), &actual.to_lowercase()),
+            ClauseOperator::Contains if predicate.exact => actual.contains(expected.as_str()),
+            ClauseOperator::Contains => actual.to_lowercase().contains(&expected.to_lowercase()),
+            op => compare(op, actual, expected),
+        },
+        (Value::Int(actual), Value::Int(expected)) => match positive {
+            ClauseOperator::Contains => false,
+            op => compare(op, actual, expected),
+        },
+        (Value::Bool(actual), Value::Bool(expected)) => {
+            positive == ClauseOperator::Eq && actual == expected
+        }
+        _ => false,
+    });
+
+    result != predicate.operator.is_negated()
+}
+
+fn compare<T: Ord + ?Sized>(op: ClauseOperator, actual: &T, expected: &T) -> bool {
+    match op {
+        ClauseOperator::Eq => actual == expected,
+        ClauseOperator::Gt => actual > expected,
+        ClauseOperator::Ge => actual >= expected,
+        ClauseOperator::Lt => actual < expected,
+        ClauseOperator::Le => actual <= expected,
+        _ => false,
+    }
+}
+
+/// Match `text` against `pattern`, where `*` in the pattern matches any sequence of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, consumed)) = backtrack {
+            p = star + 1;
+            t = consumed + 1;
+            backtrack = Some((star, consumed + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::filter;
+
+    use super::{
+        equivalent, equivalent_with, implication, implies, wildcard_match, Equivalence,
+        Implication, Semantics,
+    };
+
+    #[test]
+    fn distributive() {
+        assert_eq!(
+            equivalent(&filter("a:1+(b:2,c:3)"), &filter("(a:1+b:2),(a:1+c:3)")),
+            Equivalence::Equivalent
+        );
+    }
+
+    #[test]
+    fn absorption() {
+        assert!(equivalent(&filter("a:1,(a:1+b:2)"), &filter("a:1")).is_equivalent());
+    }
+
+    #[test]
+    fn different_with_counterexample() {
+        let Equivalence::Different(example) = equivalent(&filter("a:1+b:2"), &filter("a:1,b:2"))
+        else {
+            panic!("Filters should differ");
+        };
+
+        assert_ne!(example.left, example.right);
+        assert_eq!(
+            example.to_string(),
+            "a:1 is true, b:2 is false => left is false, right is true"
+        );
+    }
+
+    #[test]
+    fn operator_semantics() {
+        let a = filter("a:>5,a:>3");
+        let b = filter("a:>3");
+        assert!(!equivalent(&a, &b).is_equivalent());
+        assert!(equivalent_with(&a, &b, Semantics::Operators).is_equivalent());
+    }
+
+    #[test]
+    fn operator_semantics_negation() {
+        let a = filter("a:'x'+a:!'x'");
+        let b = filter("a:'x'+a:!'x'+b:1");
+        assert!(equivalent_with(&a, &b, Semantics::Operators).is_equivalent());
+    }
+
+    /// Comparing these takes exponential time, so the check gives up instead.
+    #[test]
+    fn equivalent_gives_up() {
+        let conjunction = |swap: bool| {
+            (0..22)
+                .map(|i| {
+                    if swap {
+                        format!("(b{i}:1,a{i}:1)")
+                    } else {
+                        format!("(a{i}:1,b{i}:1)")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+
+        let left = filter(&conjunction(false));
+        let right = filter(&format!("{}+(a0:1,c:1)", conjunction(true)));
+        assert_eq!(equivalent(&left, &right), Equivalence::Undecided);
+    }
+
+    #[track_caller]
+    fn check_implies(premise: &str, conclusion: &str, expected: bool) {
+        assert_eq!(
+            implies(&filter(premise), &filter(conclusion)),
+            expected,
+            "{premise} => {conclusion}"
+        );
+    }
+
+    #[test]
+    fn implies_ranges() {
+        check_implies("a:>5", "a:>=5", true);
+        check_implies("a:>=5", "a:>5", false);
+        check_implies("a:>5+a:<8", "a:>=6+a:<=7", true);
+        check_implies("a:5", "a:>4+a:<6", true);
+        check_implies("a:>4+a:<6", "a:5", true);
+        check_implies("a:>4+a:<7", "a:5", false);
+        check_implies("t:>'2022-01-01'", "t:>'2021-06-01'", true);
+    }
+
+    #[test]
+    fn implies_strings() {
+        check_implies("a:['Windows']", "a:'windows'", true);
+        check_implies("a:'windows'", "a:['Windows']", false);
+        check_implies("a:['Windows']", "a:~'dow'", true);
+        check_implies("a:~'windows'", "a:~'dow'", true);
+        check_implies("a:~'dow'", "a:~'windows'", false);
+        check_implies("a:'win*'", "a:~'WIN'", true);
+    }
+
+    #[test]
+    fn implies_negation() {
+        check_implies("a:'x'", "a:!'y'", true);
+        check_implies("a:!'x'", "a:'y'", false);
+        check_implies("a:!~'win'", "a:!~'windows'", true);
+        check_implies("a:!~'windows'", "a:!~'win'", false);
+        check_implies("a:1+b:2", "a:1", true);
+        check_implies("a:1", "a:1,b:2", true);
+        check_implies("a:1,b:2", "a:1", false);
+    }
+
+    /// Checking this takes exponential time, so the check gives up instead.
+    #[test]
+    fn implication_gives_up() {
+        let conjunction = |swap: bool| {
+            (0..22)
+                .map(|i| {
+                    if swap {
+                        format!("(b{i}:1,a{i}:1)")
+                    } else {
+                        format!("(a{i}:1,b{i}:1)")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+
+        let premise = filter(&conjunction(false));
+        let conclusion = filter(&conjunction(true));
+        assert_eq!(implication(&premise, &conclusion), Implication::Undecided);
+        assert!(!implies(&premise, &conclusion));
+    }
+
+    /// The search assigns one clause at a time, so a long filter must not need a
+    /// stack frame per clause.
+    #[test]
+    fn implication_many_clauses() {
+        let chain = (0..1000)
+            .map(|i| format!("a{i}:1"))
+            .collect::<Vec<_>>()
+            .join("+");
+        let premise = filter(&chain);
+        let conclusion = filter("a0:1");
+
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || assert!(implies(&premise, &conclusion)))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("win*", "windows"));
+        assert!(wildcard_match("*dow*", "windows"));
+        assert!(!wildcard_match("win*x", "windows"));
+        assert!(wildcard_match("*", ""));
+    }
+}
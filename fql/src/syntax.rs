@@ -26,6 +26,7 @@ pub enum SyntaxKind {
     Le,
     Tilde,
     BangTilde,
+    Dollar,
 
     /// A property is a period-delimited list of identifiers
     Property,
@@ -37,6 +38,9 @@ pub enum SyntaxKind {
 
     Operand,
 
+    /// A placeholder is `$name`, to be replaced by a literal before use.
+    Placeholder,
+
     /// A clause is `field:[operator]operand`
     Clause,
 
@@ -69,6 +73,7 @@ impl From<TokenKind> for SyntaxKind {
             TokenKind::Le => SyntaxKind::Le,
             TokenKind::Tilde => SyntaxKind::Tilde,
             TokenKind::BangTilde => SyntaxKind::BangTilde,
+            TokenKind::Dollar => SyntaxKind::Dollar,
             TokenKind::Integer => SyntaxKind::Integer,
         }
     }
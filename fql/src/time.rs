@@ -0,0 +1,103 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Format `time` as an RFC 3339 timestamp in UTC, such as `2022-04-01T12:30:00Z`.
+///
+/// Sub-second precision is truncated.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => {
+            let before = before.duration();
+            // Round towards negative infinity so the time of day stays in range.
+            let extra = i64::from(before.subsec_nanos() > 0);
+            -(before.as_secs() as i64) - extra
+        }
+    };
+
+    let days = seconds.div_euclid(86_400);
+    let time_of_day = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+/// Whether `day` exists in `month` of `year` in the proleptic Gregorian calendar,
+/// accounting for the length of the month and leap years.
+pub(crate) fn is_valid_date(year: i64, month: u32, day: u32) -> bool {
+    // Out-of-range days roll over into the next month, so don't convert back to the
+    // same date.
+    (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && civil_from_days(days_from_civil(year, month, day)) == (year, month, day)
+}
+
+/// Convert a proleptic Gregorian date into days since 1970-01-01.
+///
+/// This is Howard Hinnant's `days_from_civil` algorithm, the inverse of
+/// [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Convert days since 1970-01-01 into a proleptic Gregorian `(year, month, day)`.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{format_timestamp, is_valid_date};
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_648_816_200)),
+            "2022-04-01T12:30:00Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_millis(951_782_400_999)),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH - Duration::from_millis(500)),
+            "1969-12-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    fn valid_dates() {
+        assert!(is_valid_date(2022, 1, 31));
+        assert!(!is_valid_date(2022, 4, 31));
+        assert!(!is_valid_date(2023, 2, 29));
+        assert!(is_valid_date(2024, 2, 29));
+        assert!(!is_valid_date(1900, 2, 29));
+        assert!(is_valid_date(2000, 2, 29));
+        assert!(!is_valid_date(2022, 13, 1));
+        assert!(!is_valid_date(2022, 1, 0));
+    }
+}